@@ -1,78 +1,263 @@
 fn main() {
     let input: Vec<String> = input::read_lines("puzzles/day10.txt");
-    println!("solution 1: {}", solve1(&input));
-    println!("solution 2: {}", solve2(&input));
+    let table = BracketTable::default();
+    println!("solution 1: {}", solve1(&input, &table));
+    println!("solution 2: {}", solve2(&input, &table));
+
+    // `day10 repair [--fix-corrupted]` prints the completed lines, and with
+    // the flag also the fixes for corrupted ones
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(|s| s.as_str()) == Some("repair") {
+        let fix_corrupted = args.iter().any(|a| a == "--fix-corrupted");
+        for (idx, line) in input.iter().enumerate() {
+            let repair = match repair(line, &table, fix_corrupted) {
+                Some(repair) if repair.line != *line => repair,
+                _ => continue,
+            };
+            println!("line {}: {}", idx + 1, repair.line);
+            for fix in &repair.fixes {
+                println!("  {}", fix);
+            }
+            if !repair.completion.is_empty() {
+                println!("  append \"{}\"", repair.completion);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bracket {
+    open: char,
+    close: char,
+    // Score when `close` shows up where another bracket should close
+    corrupted: usize,
+    // Score for each `close` needed to complete a line
+    completion: usize,
 }
 
-const fn is_opening(c: char) -> bool {
-    matches!(c, '(' | '[' | '{' | '<')
+// The brackets a line can use. A bracket that opens and closes with the same
+// character (like a quote) closes when it's the innermost open one and opens
+// otherwise. Characters that aren't in the table are invalid in strict
+// tables and skipped in the others.
+#[derive(Debug, Clone, PartialEq)]
+struct BracketTable {
+    brackets: Vec<Bracket>,
+    strict: bool,
 }
 
-const fn byteval(c: char) -> u8 {
-    match c {
-        '(' | ')' => (1),
-        '[' | ']' => (2),
-        '{' | '}' => (3),
-        '<' | '>' => (4),
-        _ => unreachable!(),
+impl Default for BracketTable {
+    fn default() -> Self {
+        BracketTable::new(
+            &[
+                ('(', ')', 3, 1),
+                ('[', ']', 57, 2),
+                ('{', '}', 1197, 3),
+                ('<', '>', 25137, 4),
+            ],
+            true,
+        )
     }
 }
 
-const fn get_score(c: u8) -> usize {
-    match c {
-        1 => 3,
-        2 => 57,
-        3 => 1197,
-        4 => 25137,
-        _ => unreachable!(),
+impl BracketTable {
+    // Each bracket is (open, close, corrupted score, completion score).
+    pub fn new(brackets: &[(char, char, usize, usize)], strict: bool) -> Self {
+        let brackets = brackets
+            .iter()
+            .map(|&(open, close, corrupted, completion)| Bracket {
+                open,
+                close,
+                corrupted,
+                completion,
+            })
+            .collect();
+        BracketTable { brackets, strict }
+    }
+    pub fn opening(&self, c: char) -> Option<&Bracket> {
+        self.brackets.iter().find(|b| b.open == c)
+    }
+    pub fn closing(&self, c: char) -> Option<&Bracket> {
+        self.brackets.iter().find(|b| b.close == c)
+    }
+    pub fn corrupted_score(&self, c: char) -> usize {
+        self.closing(c).map_or(0, |b| b.corrupted)
+    }
+    pub fn completion_score(&self, c: char) -> usize {
+        self.closing(c).map_or(0, |b| b.completion)
     }
 }
 
-fn solve1(input: &[String]) -> usize {
-    let mut score: usize = 0;
-    let mut stack: Vec<u8> = Vec::with_capacity(150);
-    'main: for line in input {
-        stack.clear();
-        for cc in line.chars() {
-            let c = byteval(cc);
-            if is_opening(cc) {
-                stack.push(c);
-            } else {
-                let co = stack.pop().unwrap();
-                if co != c {
-                    score += get_score(c);
-                    continue 'main;
+#[derive(Debug, PartialEq)]
+enum LineStatus {
+    Ok,
+    // The chunk closes with the wrong character. `expected` is `None` when
+    // there was no chunk open to close.
+    Corrupted {
+        position: usize,
+        expected: Option<char>,
+        found: char,
+    },
+    // Every chunk so far is fine, but some are still open
+    Incomplete {
+        completion: String,
+    },
+    // The character isn't a bracket at all
+    Invalid {
+        position: usize,
+        char: char,
+    },
+}
+
+// Positions are character offsets from the start of the line.
+fn check_line(line: &str, table: &BracketTable) -> LineStatus {
+    let mut stack: Vec<&Bracket> = Vec::with_capacity(150);
+    for (position, c) in line.chars().enumerate() {
+        let closes_innermost = stack.last().is_some_and(|b| b.close == c);
+        match (table.opening(c), table.closing(c)) {
+            (Some(bracket), _) if !closes_innermost => stack.push(bracket),
+            (_, Some(_)) => {
+                let expected = stack.pop().map(|b| b.close);
+                if expected != Some(c) {
+                    return LineStatus::Corrupted {
+                        position,
+                        expected,
+                        found: c,
+                    };
                 }
             }
+            _ if table.strict => return LineStatus::Invalid { position, char: c },
+            _ => {}
         }
     }
 
-    score
-}
-
-fn solve2(input: &[String]) -> usize {
-    let mut scores: Vec<usize> = Vec::new();
-    let mut stack: Vec<u8> = Vec::with_capacity(150);
-    'main: for line in input {
-        let mut score: usize = 0;
-        stack.clear();
-        for cc in line.chars() {
-            let c = byteval(cc);
-            if is_opening(cc) {
-                stack.push(c);
-            } else {
-                let co = stack.pop().unwrap();
-                if co != c {
-                    continue 'main;
-                }
+    if stack.is_empty() {
+        LineStatus::Ok
+    } else {
+        LineStatus::Incomplete {
+            completion: stack.iter().rev().map(|b| b.close).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Fix {
+    // Swap a wrong closing character for the one the chunk needs
+    Replace {
+        position: usize,
+        found: char,
+        with: char,
+    },
+    // Drop a closing character that has no chunk to close
+    Remove {
+        position: usize,
+        found: char,
+    },
+}
+
+impl std::fmt::Display for Fix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Fix::Replace {
+                position,
+                found,
+                with,
+            } => write!(
+                f,
+                "replace '{}' at column {} with '{}'",
+                found,
+                position + 1,
+                with
+            ),
+            Fix::Remove { position, found } => {
+                write!(f, "remove '{}' at column {}", found, position + 1)
             }
         }
-        while let Some(left) = stack.pop() {
-            score *= 5;
-            score += left as usize;
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Repair {
+    // The line with the fixes applied and the completion appended
+    line: String,
+    fixes: Vec<Fix>,
+    completion: String,
+}
+
+// Completes an incomplete line. With `fix_corrupted` every wrong closing
+// character is also swapped for the expected one (or dropped if nothing was
+// open), one at a time from the left. Lines with invalid characters, and
+// corrupted lines without `fix_corrupted`, can't be repaired. Fix positions
+// are columns of the original line, not of the partly repaired one.
+fn repair(line: &str, table: &BracketTable, fix_corrupted: bool) -> Option<Repair> {
+    let mut chars: Vec<char> = line.chars().collect();
+    // where each remaining character was in the original line
+    let mut columns: Vec<usize> = (0..chars.len()).collect();
+    let mut fixes = Vec::new();
+    loop {
+        let fixed: String = chars.iter().collect();
+        match check_line(&fixed, table) {
+            LineStatus::Ok => {
+                return Some(Repair {
+                    line: fixed,
+                    fixes,
+                    completion: String::new(),
+                })
+            }
+            LineStatus::Incomplete { completion } => {
+                return Some(Repair {
+                    line: fixed + &completion,
+                    fixes,
+                    completion,
+                })
+            }
+            LineStatus::Corrupted {
+                position,
+                expected,
+                found,
+            } if fix_corrupted => match expected {
+                Some(with) => {
+                    chars[position] = with;
+                    fixes.push(Fix::Replace {
+                        position: columns[position],
+                        found,
+                        with,
+                    });
+                }
+                None => {
+                    chars.remove(position);
+                    fixes.push(Fix::Remove {
+                        position: columns.remove(position),
+                        found,
+                    });
+                }
+            },
+            _ => return None,
         }
-        scores.push(score);
     }
+}
+
+fn solve1(input: &[String], table: &BracketTable) -> usize {
+    input
+        .iter()
+        .map(|line| match check_line(line, table) {
+            LineStatus::Corrupted { found, .. } => table.corrupted_score(found),
+            _ => 0,
+        })
+        .sum()
+}
+
+fn solve2(input: &[String], table: &BracketTable) -> usize {
+    let mut scores: Vec<usize> = input
+        .iter()
+        .filter_map(|line| match check_line(line, table) {
+            LineStatus::Incomplete { completion } => Some(
+                completion
+                    .chars()
+                    .fold(0, |score, c| score * 5 + table.completion_score(c)),
+            ),
+            _ => None,
+        })
+        .collect();
 
     scores.sort_unstable();
     scores[scores.len() / 2]
@@ -92,6 +277,146 @@ fn test1() {
         "<{([([[(<>()){}]>(<<{{".to_string(),
         "<{([{{}}[<[[[<>{}]]]>[]]".to_string(),
     ];
-    assert_eq!(solve1(&input), 26397);
-    assert_eq!(solve2(&input), 288957);
+    let table = BracketTable::default();
+    assert_eq!(solve1(&input, &table), 26397);
+    assert_eq!(solve2(&input, &table), 288957);
+}
+
+#[test]
+fn test_check_line() {
+    let table = BracketTable::default();
+    assert_eq!(check_line("([]{<>})", &table), LineStatus::Ok);
+    assert_eq!(check_line("", &table), LineStatus::Ok);
+    assert_eq!(
+        check_line("{([(<{}[<>[]}>{[]{[(<()>", &table),
+        LineStatus::Corrupted {
+            position: 12,
+            expected: Some(']'),
+            found: '}'
+        }
+    );
+    assert_eq!(
+        check_line("[({(<(())[]>[[{[]{<()<>>", &table),
+        LineStatus::Incomplete {
+            completion: "}}]])})]".to_string()
+        }
+    );
+    assert_eq!(
+        check_line(")()", &table),
+        LineStatus::Corrupted {
+            position: 0,
+            expected: None,
+            found: ')'
+        }
+    );
+    assert_eq!(
+        check_line("(a)", &table),
+        LineStatus::Invalid {
+            position: 1,
+            char: 'a'
+        }
+    );
+}
+
+#[test]
+fn test_bracket_table() {
+    // tags with quoted attributes, anything else is text
+    let table = BracketTable::new(&[('<', '>', 1, 1), ('"', '"', 2, 2)], false);
+    assert_eq!(check_line("<a href=\"x\">text</a>", &table), LineStatus::Ok);
+    assert_eq!(
+        check_line("<a href=\"x", &table),
+        LineStatus::Incomplete {
+            completion: "\">".to_string()
+        }
+    );
+    assert_eq!(
+        check_line("<b>>", &table),
+        LineStatus::Corrupted {
+            position: 3,
+            expected: None,
+            found: '>'
+        }
+    );
+    assert_eq!(
+        check_line("<a \"b>\"", &table),
+        LineStatus::Corrupted {
+            position: 5,
+            expected: Some('"'),
+            found: '>'
+        }
+    );
+    assert_eq!(table.corrupted_score('>'), 1);
+    assert_eq!(table.completion_score('"'), 2);
+
+    let input = vec!["<\"".to_string(), "<\"\"".to_string(), "\"".to_string()];
+    // the middle of 1, 2 and 2 * 5 + 1
+    assert_eq!(solve2(&input, &table), 2);
+}
+
+#[test]
+fn test_repair() {
+    let table = BracketTable::default();
+    assert_eq!(
+        repair("[({(<(())[]>[[{[]{<()<>>", &table, false),
+        Some(Repair {
+            line: "[({(<(())[]>[[{[]{<()<>>}}]])})]".to_string(),
+            fixes: vec![],
+            completion: "}}]])})]".to_string(),
+        })
+    );
+    assert_eq!(repair("{([(<{}[<>[]}>{[]{[(<()>", &table, false), None);
+    assert_eq!(repair("(x", &table, true), None);
+
+    let fixed = repair("{([(<{}[<>[]}>{[]{[(<()>", &table, true).unwrap();
+    assert_eq!(
+        fixed.fixes,
+        vec![Fix::Replace {
+            position: 12,
+            found: '}',
+            with: ']'
+        }]
+    );
+    assert_eq!(check_line(&fixed.line, &table), LineStatus::Ok);
+    assert_eq!(
+        fixed.line,
+        "{([(<{}[<>[]]>{[]{[(<()>".to_string() + &fixed.completion
+    );
+
+    let fixed = repair("())(", &table, true).unwrap();
+    assert_eq!(
+        fixed.fixes,
+        vec![Fix::Remove {
+            position: 2,
+            found: ')'
+        }]
+    );
+    assert_eq!(fixed.line, "()()");
+    assert_eq!(fixed.fixes[0].to_string(), "remove ')' at column 3");
+
+    // positions stay those of the original line after earlier removals
+    let fixed = repair("())())", &table, true).unwrap();
+    assert_eq!(
+        fixed.fixes,
+        vec![
+            Fix::Remove {
+                position: 2,
+                found: ')'
+            },
+            Fix::Remove {
+                position: 5,
+                found: ')'
+            }
+        ]
+    );
+    assert_eq!(fixed.line, "()()");
+    assert_eq!(fixed.fixes[1].to_string(), "remove ')' at column 6");
+    let fixed = repair("())(]", &table, true).unwrap();
+    assert_eq!(
+        fixed.fixes[1],
+        Fix::Replace {
+            position: 4,
+            found: ']',
+            with: ')'
+        }
+    );
 }
@@ -1,91 +1,377 @@
-use std::io::BufRead;
+use std::{
+    io::{self, BufRead, Write},
+    thread,
+    time::Duration,
+};
 
-struct OctopiField<const N: usize> {
-    field: [[u8; N]; N],
+#[derive(Clone)]
+struct OctopiField {
+    width: usize,
+    height: usize,
+    // energy of the octopus at (x, y) is at y * width + x
+    field: Vec<u8>,
     flashes: u32,
 }
 
-impl<const N: usize> OctopiField<N> {
-    pub fn new<I: ToString>(iter: impl Iterator<Item = I>) -> Self {
-        let mut field = [[0; N]; N];
-        for (y, line) in iter.enumerate() {
-            for (x, c) in line.to_string().chars().enumerate() {
-                field[y][x] = c as u8 - b'0';
-            }
+#[derive(Debug, PartialEq)]
+enum FieldError {
+    Empty,
+    // Line `y` has `len` octopi where the first line has `width`
+    UnevenLine { y: usize, len: usize, width: usize },
+    NotAnEnergy { x: usize, y: usize, c: char },
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldError::Empty => write!(f, "the field has no octopi"),
+            FieldError::UnevenLine { y, len, width } => write!(
+                f,
+                "line {} has {} octopi, the field needs {} per line",
+                y + 1,
+                len,
+                width
+            ),
+            FieldError::NotAnEnergy { x, y, c } => write!(
+                f,
+                "line {}, column {}: '{}' is not an energy level",
+                y + 1,
+                x + 1,
+                c
+            ),
         }
-        OctopiField { field, flashes: 0 }
     }
-    pub fn step(&mut self) -> u32 {
-        let flashes_before = self.flashes;
-        for y in 0..N {
-            for x in 0..N {
-                if self.field[y][x] == 0 {
-                    self.field[y][x] = u8::MAX
-                }
+}
+
+impl OctopiField {
+    // Flashes spread to all eight neighbours, so every line has to hold as
+    // many octopi as the first one for the grid to make sense.
+    pub fn new<I: ToString>(iter: impl Iterator<Item = I>) -> Result<Self, FieldError> {
+        let lines: Vec<Vec<char>> = iter
+            .map(|line| line.to_string().trim_end().chars().collect())
+            .collect();
+        let width = lines.first().map_or(0, |line| line.len());
+        if width == 0 {
+            return Err(FieldError::Empty);
+        }
+        let mut field = Vec::with_capacity(width * lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            if line.len() != width {
+                return Err(FieldError::UnevenLine {
+                    y,
+                    len: line.len(),
+                    width,
+                });
+            }
+            for (x, &c) in line.iter().enumerate() {
+                let energy = c.to_digit(10).ok_or(FieldError::NotAnEnergy { x, y, c })?;
+                field.push(energy as u8);
             }
         }
-        for y in 0..N {
-            for x in 0..N {
-                let val = &mut self.field[y][x];
-                if *val == 0 {
-                    continue;
-                }
-                if *val == u8::MAX {
-                    *val = 1;
+        Ok(OctopiField {
+            width,
+            height: lines.len(),
+            field,
+            flashes: 0,
+        })
+    }
+    pub fn len(&self) -> usize {
+        self.field.len()
+    }
+    // The energy levels, with the given cells in bold yellow.
+    pub fn render(&self, highlight: &[(usize, usize)]) -> String {
+        let mut lit = vec![false; self.field.len()];
+        for &(x, y) in highlight {
+            lit[y * self.width + x] = true;
+        }
+        let mut out = String::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let idx = y * self.width + x;
+                if lit[idx] {
+                    out += &format!("\x1b[1;33m{}\x1b[0m", self.field[idx]);
                 } else {
-                    *val += 1;
-                }
-                if *val > 9 {
-                    *val = 0;
-                    let mut flashed = [[false; N]; N];
-                    self.flash(x, y, &mut flashed);
+                    out += &self.field[idx].to_string();
                 }
             }
+            out.push('\n');
         }
-        self.flashes - flashes_before
-    }
-    fn flash(&mut self, x: usize, y: usize, flashed: &mut [[bool; N]; N]) {
-        self.flashes += 1;
-        flashed[y][x] = true;
-        let xs = if x == 0 { 0 } else { x - 1 };
-        let ys = if y == 0 { 0 } else { y - 1 };
-        for y in ys..N.min(y + 2) {
-            for x in xs..N.min(x + 2) {
-                let val = &mut self.field[y][x];
-                if *val == 0 {
-                    continue;
+        out
+    }
+    // The positions around a cell that are on the field.
+    fn neighbours(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        let xs = x.saturating_sub(1)..self.width.min(x + 2);
+        let ys = y.saturating_sub(1)..self.height.min(y + 2);
+        ys.flat_map(move |ny| xs.clone().map(move |nx| (nx, ny)))
+            .filter(move |&(nx, ny)| (nx, ny) != (x, y))
+    }
+    pub fn step(&mut self) -> u32 {
+        self.step_waves().iter().map(|wave| wave.len() as u32).sum()
+    }
+    // Runs one step and returns the cells that flashed, grouped by wave: the
+    // first wave is everything that flashed from the step's own energy, every
+    // later one flashed from the wave before it.
+    pub fn step_waves(&mut self) -> Vec<Vec<(usize, usize)>> {
+        let mut flashed = vec![false; self.field.len()];
+        let mut wave = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let idx = y * self.width + x;
+                self.field[idx] += 1;
+                if self.field[idx] > 9 {
+                    flashed[idx] = true;
+                    wave.push((x, y));
                 }
-                if !flashed[y][x] {
-                    if *val == u8::MAX {
-                        *val = 1;
-                    } else {
-                        *val += 1;
-                    }
-                    if *val > 9 {
-                        *val = 0;
-                        self.flash(x, y, flashed)
+            }
+        }
+
+        let mut waves = Vec::new();
+        while !wave.is_empty() {
+            let mut next = Vec::new();
+            for &(x, y) in &wave {
+                for (nx, ny) in self.neighbours(x, y) {
+                    let idx = ny * self.width + nx;
+                    self.field[idx] += 1;
+                    if self.field[idx] > 9 && !flashed[idx] {
+                        flashed[idx] = true;
+                        next.push((nx, ny));
                     }
                 }
             }
+            waves.push(wave);
+            wave = next;
+        }
+
+        for (val, flashed) in self.field.iter_mut().zip(flashed) {
+            if flashed {
+                *val = 0;
+            }
         }
+        self.flashes += waves.iter().map(|wave| wave.len() as u32).sum::<u32>();
+        waves
     }
 }
 
 fn main() {
-    let mut input = OctopiField::<10>::new(
+    let input = OctopiField::new(
         input::read_file("puzzles/day11.txt")
             .lines()
             .map(|l| l.unwrap()),
     );
+    let input = match input {
+        Ok(field) => field,
+        Err(e) => {
+            println!("invalid octopus field: {}", e);
+            return;
+        }
+    };
+    let mut field = input.clone();
     for _ in 0..100 {
-        input.step();
+        field.step();
     }
-    println!("solution 1: {}", input.flashes);
-    let mut i = 101;
-    while input.step() != 100 {
-        i += 1;
+    println!("solution 1: {}", field.flashes);
+    match first_sync(&input, MAX_STEPS) {
+        Ok(step) => println!("solution 2: {}", step),
+        Err(e) => println!("solution 2: {}", e),
+    }
+
+    // `day11 flashes <steps>` counts the flashes over any number of steps,
+    // `day11 animate [steps] [delay ms]` plays the field in the terminal
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let ["animate", ref rest @ ..] = args.iter().map(|s| s.as_str()).collect::<Vec<_>>()[..] {
+        let steps = rest
+            .first()
+            .map_or(100, |s| s.parse().expect("failed to parse steps"));
+        let delay = rest
+            .get(1)
+            .map_or(100, |s| s.parse().expect("failed to parse delay"));
+        animate(
+            &mut input.clone(),
+            steps,
+            Duration::from_millis(delay),
+            &mut io::stdout().lock(),
+        )
+        .expect("failed to draw the field");
+    }
+    if let ["flashes", steps] = args.iter().map(|s| s.as_str()).collect::<Vec<_>>()[..] {
+        let steps = steps.parse().expect("failed to parse steps");
+        match find_cycle(&input, MAX_STEPS) {
+            Ok(cycle) => println!(
+                "flashes after {} steps: {}",
+                steps,
+                cycle.flashes_after(steps)
+            ),
+            Err(e) => println!("flashes after {} steps: {}", steps, e),
+        }
+    }
+}
+
+// Draws the field one step at a time, with the octopi that flashed in the
+// step highlighted, waiting `delay` between frames.
+fn animate<W: Write>(
+    field: &mut OctopiField,
+    steps: usize,
+    delay: Duration,
+    out: &mut W,
+) -> io::Result<()> {
+    let mut flashed = Vec::new();
+    for step in 0..=steps {
+        if step > 0 {
+            flashed = field.step_waves().concat();
+        }
+        // clear the screen and go back to the top left
+        write!(out, "\x1b[2J\x1b[H")?;
+        writeln!(
+            out,
+            "step {}: {} flashed, {} in total",
+            step,
+            flashed.len(),
+            field.flashes
+        )?;
+        write!(out, "{}", field.render(&flashed))?;
+        out.flush()?;
+        if step < steps {
+            thread::sleep(delay);
+        }
+    }
+    Ok(())
+}
+
+// How far the cycle search goes before giving up.
+const MAX_STEPS: usize = 1_000_000;
+
+// From step `start` on, the field goes through the same `length` states
+// over and over.
+#[derive(Debug, PartialEq)]
+struct Cycle {
+    start: usize,
+    length: usize,
+    // Flashes during each step up to the end of the first run of the cycle
+    flashes: Vec<u32>,
+}
+
+impl Cycle {
+    pub fn flashes_after(&self, steps: u64) -> u64 {
+        let sum = |steps: &[u32]| steps.iter().map(|&f| f as u64).sum::<u64>();
+        if steps <= self.flashes.len() as u64 {
+            return sum(&self.flashes[..steps as usize]);
+        }
+        let cycle = &self.flashes[self.start..];
+        let (runs, rest) = (
+            (steps - self.start as u64) / self.length as u64,
+            (steps - self.start as u64) % self.length as u64,
+        );
+        sum(&self.flashes[..self.start]) + runs * sum(cycle) + sum(&cycle[..rest as usize])
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum SimError {
+    // The field settled into a cycle that never has every octopus flash
+    NeverSyncs { start: usize, length: usize },
+    // No state repeated within this many steps
+    TooManySteps(usize),
+}
+
+impl std::fmt::Display for SimError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SimError::NeverSyncs { start, length } => write!(
+                f,
+                "never synchronises, it repeats every {} steps from step {}",
+                length, start
+            ),
+            SimError::TooManySteps(steps) => write!(f, "no cycle within {} steps", steps),
+        }
+    }
+}
+
+// Brent's cycle detection. The hare steps through the states one at a time
+// while the tortoise waits at powers of two, so only a couple of copies of
+// the field are kept however long it takes for a state to come back.
+// `visit` sees the flashes of every step in order and can stop the search
+// by returning true, which gives `Ok(None)`; otherwise the cycle's start and
+// length are returned.
+fn detect_cycle(
+    field: &OctopiField,
+    max_steps: usize,
+    mut visit: impl FnMut(usize, u32) -> bool,
+) -> Result<Option<(usize, usize)>, SimError> {
+    // The hare is first level with the tortoise less than 3 * (start +
+    // length) steps in, so any cycle within `max_steps` is found by then.
+    let limit = max_steps.saturating_mul(3);
+    let mut tortoise = field.clone();
+    let mut hare = field.clone();
+    let (mut power, mut length) = (1, 1);
+    let mut step = 1;
+    if visit(step, hare.step()) {
+        return Ok(None);
+    }
+    while hare.field != tortoise.field {
+        if step >= limit {
+            return Err(SimError::TooManySteps(max_steps));
+        }
+        if power == length {
+            tortoise.clone_from(&hare);
+            power *= 2;
+            length = 0;
+        }
+        step += 1;
+        if visit(step, hare.step()) {
+            return Ok(None);
+        }
+        length += 1;
+    }
+
+    // with the hare a cycle ahead, they meet where the cycle starts
+    let mut tortoise = field.clone();
+    let mut hare = field.clone();
+    for _ in 0..length {
+        hare.step();
+    }
+    let mut start = 0;
+    while hare.field != tortoise.field {
+        tortoise.step();
+        hare.step();
+        start += 1;
+    }
+    if start + length > max_steps {
+        return Err(SimError::TooManySteps(max_steps));
+    }
+    Ok(Some((start, length)))
+}
+
+// Finds where the field starts repeating, and the flashes of every step
+// until then.
+fn find_cycle(field: &OctopiField, max_steps: usize) -> Result<Cycle, SimError> {
+    let (start, length) = detect_cycle(field, max_steps, |_, _| false)?.unwrap();
+    let mut field = field.clone();
+    let flashes = (0..start + length).map(|_| field.step()).collect();
+    Ok(Cycle {
+        start,
+        length,
+        flashes,
+    })
+}
+
+// The first step where every octopus flashes. Stepping stops there, a field
+// only runs until it repeats if it never syncs.
+fn first_sync(field: &OctopiField, max_steps: usize) -> Result<usize, SimError> {
+    let mut synced = None;
+    let cycle = detect_cycle(field, max_steps, |step, flashes| {
+        // the hare can run past `max_steps` while looking for the cycle
+        if step <= max_steps && flashes as usize == field.len() {
+            synced = Some(step);
+        }
+        synced.is_some()
+    })?;
+    // a cycle within `max_steps` has been stepped through in full, so there
+    // can't be a later sync
+    match (synced, cycle) {
+        (Some(step), _) => Ok(step),
+        (None, Some((start, length))) => Err(SimError::NeverSyncs { start, length }),
+        (None, None) => unreachable!(),
     }
-    println!("solution 2: {}", i);
 }
 
 #[test]
@@ -100,7 +386,7 @@ fn test_flashes() {
 6882881134
 4846848554
 5283751526";
-    let mut input = OctopiField::<10>::new(input.lines());
+    let mut input = OctopiField::new(input.lines()).unwrap();
     for _ in 0..10 {
         input.step();
     }
@@ -119,7 +405,136 @@ fn test_simple() {
 19191
 19991
 11111";
-    let mut input = OctopiField::<5>::new(input.lines());
+    let mut input = OctopiField::new(input.lines()).unwrap();
     assert_eq!(input.step(), 9);
     assert_eq!(input.step(), 0);
 }
+
+#[test]
+fn test_rectangular() {
+    let input = "19991
+19191
+19991";
+    let mut input = OctopiField::new(input.lines()).unwrap();
+    assert_eq!((input.width, input.height, input.len()), (5, 3, 15));
+    assert_eq!(input.step(), 9);
+    assert_eq!(input.field[5..10], [5, 0, 0, 0, 5]);
+
+    assert_eq!(
+        OctopiField::new("123\n45\n".lines()).err(),
+        Some(FieldError::UnevenLine {
+            y: 1,
+            len: 2,
+            width: 3
+        })
+    );
+    assert_eq!(
+        OctopiField::new("123\n12-\n".lines()).err(),
+        Some(FieldError::NotAnEnergy { x: 2, y: 1, c: '-' })
+    );
+    assert_eq!(
+        FieldError::NotAnEnergy { x: 2, y: 1, c: '-' }.to_string(),
+        "line 2, column 3: '-' is not an energy level"
+    );
+    assert_eq!(OctopiField::new("".lines()).err(), Some(FieldError::Empty));
+}
+
+#[test]
+fn test_waves() {
+    let input = "11111
+19991
+19191
+19991
+11111";
+    let mut input = OctopiField::new(input.lines()).unwrap();
+    let waves = input.step_waves();
+    assert_eq!(
+        waves,
+        vec![
+            vec![
+                (1, 1),
+                (2, 1),
+                (3, 1),
+                (1, 2),
+                (3, 2),
+                (1, 3),
+                (2, 3),
+                (3, 3)
+            ],
+            vec![(2, 2)],
+        ]
+    );
+    assert_eq!(input.flashes, 9);
+    assert!(input.step_waves().is_empty());
+
+    // one flash sets off the whole line, a wave at a time
+    let mut input = OctopiField::new(["9888888888"].iter()).unwrap();
+    let waves = input.step_waves();
+    assert_eq!(waves.len(), 10);
+    assert!(waves.iter().enumerate().all(|(i, w)| w == &vec![(i, 0)]));
+}
+
+#[test]
+fn test_cycles() {
+    let input = "5483143223
+2745854711
+5264556173
+6141336146
+6357385478
+4167524645
+2176841721
+6882881134
+4846848554
+5283751526";
+    let input = OctopiField::new(input.lines()).unwrap();
+    assert_eq!(first_sync(&input, MAX_STEPS), Ok(195));
+    let cycle = find_cycle(&input, MAX_STEPS).unwrap();
+    assert_eq!((cycle.start, cycle.length), (195, 10));
+    assert_eq!(cycle.flashes_after(10), 204);
+    assert_eq!(cycle.flashes_after(100), 1656);
+    assert_eq!(cycle.flashes_after(1_000_000_000_000), 10_000_000_001_125);
+    assert_eq!(find_cycle(&input, 100), Err(SimError::TooManySteps(100)));
+    // the sync is found well before the field repeats
+    assert_eq!(first_sync(&input, 195), Ok(195));
+    assert_eq!(first_sync(&input, 100), Err(SimError::TooManySteps(100)));
+    let mut steps = 0;
+    detect_cycle(&input, MAX_STEPS, |step, _| {
+        steps = step;
+        false
+    })
+    .unwrap();
+    assert!(steps < 3 * (195 + 10));
+
+    let input = OctopiField::new(["123"].iter()).unwrap();
+    assert_eq!(
+        first_sync(&input, MAX_STEPS),
+        Err(SimError::NeverSyncs {
+            start: 7,
+            length: 9
+        })
+    );
+    let cycle = find_cycle(&input, MAX_STEPS).unwrap();
+    assert_eq!(cycle.flashes_after(20), 6);
+    assert_eq!(cycle.flashes_after(1_000_000_000_000), 333_333_333_333);
+}
+
+#[test]
+fn test_render() {
+    let mut input = OctopiField::new(["191", "111"].iter()).unwrap();
+    assert_eq!(input.render(&[]), "191\n111\n");
+    let flashed = input.step_waves().concat();
+    assert_eq!(input.render(&flashed), "3\x1b[1;33m0\x1b[0m3\n333\n");
+
+    let mut out = Vec::new();
+    animate(
+        &mut OctopiField::new(["191", "111"].iter()).unwrap(),
+        2,
+        Duration::ZERO,
+        &mut out,
+    )
+    .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(out.matches("\x1b[2J").count(), 3);
+    assert!(out.contains("step 1: 1 flashed, 1 in total\n3\x1b[1;33m0\x1b[0m3\n333\n"));
+    assert!(out.ends_with("step 2: 0 flashed, 1 in total\n414\n444\n"));
+}
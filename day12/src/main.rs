@@ -1,4 +1,8 @@
-use std::{fmt::Debug, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Debug},
+    str::FromStr,
+};
 
 #[derive(Debug, PartialEq)]
 struct Edge<T: Debug + PartialEq>(T, T);
@@ -13,56 +17,193 @@ impl FromStr for Edge<String> {
     }
 }
 
+fn is_small_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+}
+
+// How often the small caves on a path can be visited: `revisited_caves` of
+// them may be entered up to `max_revisits` more times after the first.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct VisitPolicy {
+    max_revisits: u32,
+    revisited_caves: u32,
+}
+
+impl VisitPolicy {
+    // Part 2's rule, a single small cave can be visited twice.
+    pub fn one_twice() -> Self {
+        VisitPolicy {
+            max_revisits: 1,
+            revisited_caves: 1,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum GraphError {
+    BigNeighbours(String, String),
+    TooManySmallCaves(usize),
+    MissingCave(String),
+    SameEndpoints(String),
+    // The tunnel on this line has an end without a name
+    UnnamedCave(usize),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GraphError::BigNeighbours(a, b) => write!(
+                f,
+                "big caves {} and {} are connected, paths through them never end",
+                a, b
+            ),
+            GraphError::TooManySmallCaves(n) => {
+                write!(f, "{} small caves, at most 64 are supported", n)
+            }
+            GraphError::MissingCave(name) => write!(f, "there is no cave named {}", name),
+            GraphError::SameEndpoints(name) => {
+                write!(f, "paths can't both start and end at {}", name)
+            }
+            GraphError::UnnamedCave(line) => {
+                write!(
+                    f,
+                    "the tunnel on line {} leads to a cave with no name",
+                    line + 1
+                )
+            }
+        }
+    }
+}
+
+// The revisits a path has used so far: how many caves have had one, and the
+// extra visits each of them had. Once no more caves can be revisited, the
+// ones that can't take any more visits are dropped, since they behave like
+// any other visited cave from then on.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+struct Revisits {
+    caves: u32,
+    extra: Vec<(u64, u32)>,
+}
+
+impl Revisits {
+    fn enter(&self, bit: u64, max_revisits: u32, revisited_caves: u32) -> Option<Self> {
+        let mut next = self.clone();
+        match next.extra.iter_mut().find(|(b, _)| *b == bit) {
+            Some((_, n)) if *n < max_revisits => *n += 1,
+            Some(_) => return None,
+            None if next.caves < revisited_caves && max_revisits > 0 => {
+                next.caves += 1;
+                next.extra.push((bit, 1));
+                next.extra.sort_unstable();
+            }
+            None => return None,
+        }
+        if next.caves == revisited_caves {
+            next.extra.retain(|(_, n)| *n < max_revisits);
+        }
+        Some(next)
+    }
+}
+
 struct CaveGraph {
+    lookup: Vec<String>,
+    adjacency: Vec<Vec<usize>>,
+    // a bit of the visited set for every small cave
+    small_bits: Vec<Option<u64>>,
     start: usize,
     end: usize,
-    lookup: Vec<String>,
-    matrix: Vec<Vec<bool>>,
 }
 
 impl CaveGraph {
-    pub fn new(edges: &[Edge<String>]) -> Self {
-        let assigned = Self::assign_vertex_ids(edges);
-        let start = assigned.iter().position(|c| *c == "start").unwrap();
-        let end = assigned.iter().position(|c| *c == "end").unwrap();
+    // A graph with the usual "start" and "end" caves.
+    pub fn new(edges: &[Edge<String>]) -> Result<Self, GraphError> {
+        Self::with_endpoints(edges, "start", "end")
+    }
 
-        let edges = Self::optimize_edge_list(edges, &assigned);
+    // A graph whose paths go from `start` to `end`. The tunnels and both
+    // endpoints are checked before anything is searched, so a bad graph is
+    // rejected up front.
+    pub fn with_endpoints(
+        edges: &[Edge<String>],
+        start: &str,
+        end: &str,
+    ) -> Result<Self, GraphError> {
+        if let Some(line) = edges.iter().position(|e| e.0.is_empty() || e.1.is_empty()) {
+            return Err(GraphError::UnnamedCave(line));
+        }
+        if start == end {
+            return Err(GraphError::SameEndpoints(start.to_string()));
+        }
+        let (assigned, ids) = Self::assign_vertex_ids(edges);
+        let cave = |name: &str| {
+            ids.get(name)
+                .copied()
+                .ok_or_else(|| GraphError::MissingCave(name.to_string()))
+        };
+        let (start, end) = (cave(start)?, cave(end)?);
+        if let Some(edge) = edges
+            .iter()
+            .find(|e| !is_small_name(&e.0) && !is_small_name(&e.1))
+        {
+            return Err(GraphError::BigNeighbours(edge.0.clone(), edge.1.clone()));
+        }
+        let small = assigned.iter().filter(|name| is_small_name(name)).count();
+        if small > 64 {
+            return Err(GraphError::TooManySmallCaves(small));
+        }
 
-        let mut matrix = vec![vec![false; assigned.len()]; assigned.len()];
-        for edge in edges {
-            matrix[edge.0][edge.1] = true;
-            matrix[edge.1][edge.0] = true;
+        let mut adjacency = vec![Vec::new(); assigned.len()];
+        for edge in Self::optimize_edge_list(edges, &ids) {
+            adjacency[edge.0].push(edge.1);
+            if edge.0 != edge.1 {
+                adjacency[edge.1].push(edge.0);
+            }
         }
 
-        CaveGraph {
+        let mut small_bits = vec![None; assigned.len()];
+        let mut bit = 0;
+        for (vid, name) in assigned.iter().enumerate() {
+            if is_small_name(name) {
+                small_bits[vid] = Some(1 << bit);
+                bit += 1;
+            }
+        }
+
+        Ok(CaveGraph {
+            lookup: assigned,
+            adjacency,
+            small_bits,
             start,
             end,
-            lookup: assigned,
-            matrix,
-        }
+        })
     }
 
-    fn assign_vertex_ids(edges: &[Edge<String>]) -> Vec<String> {
+    // Numbers the caves in the order they first appear.
+    fn assign_vertex_ids(edges: &[Edge<String>]) -> (Vec<String>, HashMap<String, usize>) {
         let mut vertices = Vec::with_capacity(edges.len() / 2);
+        let mut ids = HashMap::with_capacity(edges.len() / 2);
         for edge in edges {
-            if !vertices.contains(&edge.0) {
-                vertices.push(edge.0.clone());
-            }
-            if !vertices.contains(&edge.1) {
-                vertices.push(edge.1.clone());
+            for name in [&edge.0, &edge.1] {
+                if !ids.contains_key(name) {
+                    ids.insert(name.clone(), vertices.len());
+                    vertices.push(name.clone());
+                }
             }
         }
-        vertices
+        (vertices, ids)
     }
 
-    fn optimize_edge_list(edges: &[Edge<String>], lookup: &[String]) -> Vec<Edge<usize>> {
+    // Each tunnel once, however many times or in which direction it's listed.
+    fn optimize_edge_list(
+        edges: &[Edge<String>],
+        ids: &HashMap<String, usize>,
+    ) -> Vec<Edge<usize>> {
+        let mut seen = HashSet::with_capacity(edges.len());
         let mut new = Vec::with_capacity(edges.len());
         for edge in edges {
-            let start = lookup.iter().position(|c| *c == edge.0).unwrap();
-            let end = lookup.iter().position(|c| *c == edge.1).unwrap();
-            let edge = Edge(start, end);
-            if !new.contains(&edge) {
-                new.push(Edge(start, end));
+            let (a, b) = (ids[&edge.0], ids[&edge.1]);
+            if seen.insert((a.min(b), a.max(b))) {
+                new.push(Edge(a, b));
             }
         }
 
@@ -70,83 +211,304 @@ impl CaveGraph {
     }
 
     fn is_small(&self, vid: usize) -> bool {
-        self.lookup[vid]
-            .chars()
-            .next()
-            .unwrap()
-            .is_ascii_lowercase()
+        self.small_bits[vid].is_some()
+    }
+
+    pub fn neighbors(&self, v: usize) -> &[usize] {
+        &self.adjacency[v]
     }
 
-    fn neighbors(&self, v: usize) -> impl Iterator<Item = usize> + '_ {
-        self.matrix[v]
+    // Every path allowed by the policy.
+    pub fn paths(&self, policy: &VisitPolicy) -> Paths<'_> {
+        let mut visits = vec![0; self.lookup.len()];
+        visits[self.start] = 1;
+        Paths {
+            graph: self,
+            stack: vec![(self.start, self.ordered_neighbors(self.start, false), false)],
+            visits,
+            max_revisits: policy.max_revisits,
+            revisited_caves: policy.revisited_caves,
+            revisited: 0,
+            max_len: None,
+            sorted: false,
+        }
+    }
+
+    // Neighbours in the order they're popped off a `Paths` frame.
+    fn ordered_neighbors(&self, v: usize, sorted: bool) -> Vec<usize> {
+        let mut neighbors = self.neighbors(v).to_vec();
+        if sorted {
+            neighbors.sort_by(|a, b| self.lookup[*a].cmp(&self.lookup[*b]));
+        }
+        neighbors.reverse();
+        neighbors
+    }
+
+    // The caves as a Graphviz graph: small caves are circles, big ones
+    // filled boxes, the start and end are drawn doubled, and the caves and
+    // tunnels along `path` are red.
+    pub fn dot(&self, path: &[&str]) -> String {
+        let on_path: Vec<bool> = self
+            .lookup
             .iter()
-            .enumerate()
-            .filter(|(_, b)| **b)
-            .map(|(i, _)| i)
+            .map(|name| path.contains(&name.as_str()))
+            .collect();
+        let mut out = String::from("graph caves {\n");
+        for (v, name) in self.lookup.iter().enumerate() {
+            let mut attrs = vec![if self.is_small(v) {
+                "shape=circle"
+            } else {
+                "shape=box, style=filled, fillcolor=lightgrey"
+            }];
+            if v == self.start || v == self.end {
+                attrs.push("peripheries=2");
+            }
+            if on_path[v] {
+                attrs.push("color=red, fontcolor=red");
+            }
+            out += &format!("    {:?} [{}];\n", name, attrs.join(", "));
+        }
+        for v in 0..self.lookup.len() {
+            for &n in self.neighbors(v).iter().filter(|&&n| n > v) {
+                let (a, b) = (self.lookup[v].as_str(), self.lookup[n].as_str());
+                let walked = path
+                    .windows(2)
+                    .any(|w| (w[0], w[1]) == (a, b) || (w[0], w[1]) == (b, a));
+                out += &format!("    {:?} -- {:?}", a, b);
+                if walked {
+                    out += " [color=red, penwidth=2]";
+                }
+                out += ";\n";
+            }
+        }
+        out += "}\n";
+        out
     }
 
     pub fn solve1(&self) -> usize {
-        let seen = vec![false; self.lookup.len()];
-        self.find_paths1(self.start, &seen)
+        self.count_paths(&VisitPolicy::default())
     }
 
     pub fn solve2(&self) -> usize {
-        let seen = vec![false; self.lookup.len()];
-        self.find_paths2(self.start, &seen, true)
+        self.count_paths(&VisitPolicy::one_twice())
     }
 
-    fn find_paths1(&self, v: usize, seen: &[bool]) -> usize {
-        if v == self.end {
+    pub fn count_paths(&self, policy: &VisitPolicy) -> usize {
+        let search = Search {
+            graph: self,
+            policy,
+        };
+        let mut memo = HashMap::new();
+        // the start is never entered again, so it doesn't need a bit
+        search.count_from(self.start, 0, Revisits::default(), &mut memo)
+    }
+}
+
+struct Search<'a> {
+    graph: &'a CaveGraph,
+    policy: &'a VisitPolicy,
+}
+
+impl Search<'_> {
+    // Paths from `v` to the end, given the small caves already on the path
+    // and the revisits used. The count doesn't depend on the order those
+    // caves were visited in, so it's shared between all paths that reach the
+    // same state.
+    fn count_from(
+        &self,
+        v: usize,
+        visited: u64,
+        revisits: Revisits,
+        memo: &mut HashMap<(usize, u64, Revisits), usize>,
+    ) -> usize {
+        if v == self.graph.end {
             return 1;
         }
+        let key = (v, visited, revisits);
+        if let Some(&paths) = memo.get(&key) {
+            return paths;
+        }
+        let (_, visited, revisits) = &key;
         let mut paths = 0;
-        let mut seen = seen.to_owned();
-        if seen[v] {
-            if v == self.start {
-                return 0;
-            }
-            if self.is_small(v) {
-                return 0;
+        for &n in self.graph.neighbors(v) {
+            if n == self.graph.start {
+                continue;
             }
+            paths += match self.graph.small_bits[n] {
+                Some(bit) if visited & bit != 0 => {
+                    match revisits.enter(bit, self.policy.max_revisits, self.policy.revisited_caves)
+                    {
+                        Some(next) => self.count_from(n, *visited, next, memo),
+                        None => continue,
+                    }
+                }
+                Some(bit) => self.count_from(n, visited | bit, revisits.clone(), memo),
+                None => self.count_from(n, *visited, revisits.clone(), memo),
+            };
         }
-        for n in self.neighbors(v) {
-            seen[v] = true;
-            paths += self.find_paths1(n, &seen);
-        }
+        memo.insert(key, paths);
         paths
     }
+}
 
-    fn find_paths2(&self, v: usize, seen: &[bool], mut twice: bool) -> usize {
-        if v == self.end {
-            return 1;
+// Depth first walk over the cave paths, yielding the cave names of each path
+// as it reaches the end.
+struct Paths<'a> {
+    graph: &'a CaveGraph,
+    // cave, neighbours still to try, and whether entering it made it one of
+    // the revisited caves
+    stack: Vec<(usize, Vec<usize>, bool)>,
+    visits: Vec<u32>,
+    max_revisits: u32,
+    revisited_caves: u32,
+    revisited: u32,
+    max_len: Option<usize>,
+    sorted: bool,
+}
+
+impl<'a> Paths<'a> {
+    // Only paths with at most `caves` caves in them, start and end included.
+    pub fn max_len(mut self, caves: usize) -> Self {
+        self.max_len = Some(caves);
+        self
+    }
+    // Yield the paths in lexicographic order of their cave names.
+    pub fn sorted(mut self) -> Self {
+        self.sorted = true;
+        for frame in self.stack.iter_mut() {
+            frame.1 = self.graph.ordered_neighbors(frame.0, true);
         }
-        let mut seen = seen.to_owned();
-        let mut paths = 0;
-        if seen[v] {
-            if v == self.start {
-                return 0;
-            }
-            if self.is_small(v) {
-                if twice {
-                    twice = false;
-                } else {
-                    return 0;
+        self
+    }
+    fn fits(&self, len: usize) -> bool {
+        self.max_len.is_none_or(|max| len <= max)
+    }
+}
+
+impl<'a> Iterator for Paths<'a> {
+    type Item = Vec<&'a str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let graph = self.graph;
+        loop {
+            let (_, neighbors, _) = self.stack.last_mut()?;
+            let n = match neighbors.pop() {
+                Some(n) => n,
+                None => {
+                    let (v, _, used) = self.stack.pop().unwrap();
+                    self.visits[v] -= 1;
+                    if used {
+                        self.revisited -= 1;
+                    }
+                    continue;
                 }
+            };
+            if n == graph.start {
+                continue;
             }
+            if n == graph.end {
+                if self.fits(self.stack.len() + 1) {
+                    let mut path: Vec<&str> = self
+                        .stack
+                        .iter()
+                        .map(|(v, _, _)| graph.lookup[*v].as_str())
+                        .collect();
+                    path.push(&graph.lookup[n]);
+                    return Some(path);
+                }
+                continue;
+            }
+            // there has to be room for the end after this cave
+            if !self.fits(self.stack.len() + 2) {
+                continue;
+            }
+            let mut used = false;
+            if self.visits[n] > 0 && graph.is_small(n) {
+                if self.visits[n] > self.max_revisits {
+                    continue;
+                }
+                if self.visits[n] == 1 {
+                    if self.revisited == self.revisited_caves {
+                        continue;
+                    }
+                    used = true;
+                    self.revisited += 1;
+                }
+            }
+            self.visits[n] += 1;
+            self.stack
+                .push((n, graph.ordered_neighbors(n, self.sorted), used));
         }
-        for n in self.neighbors(v) {
-            seen[v] = true;
-            paths += self.find_paths2(n, &seen, twice);
-        }
-        paths
     }
 }
 
 fn main() {
+    // `day12 paths <1|2> [max caves]` lists the paths under either rule,
+    // `day12 count <max revisits> <revisited caves> [start end]` counts them
+    // under any other, `day12 dot [path]` prints the caves for Graphviz with
+    // a comma separated path highlighted
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+
     let edges: Vec<Edge<String>> = input::read_lines("puzzles/day12.txt");
     let graph = CaveGraph::new(&edges);
-    println!("solution 1: {}", graph.solve1());
-    println!("solution 2: {}", graph.solve2());
+    match &graph {
+        Ok(graph) => {
+            println!("solution 1: {}", graph.solve1());
+            println!("solution 2: {}", graph.solve2());
+        }
+        Err(e) => eprintln!("invalid caves: {}", e),
+    }
+
+    match args[..] {
+        ["count", max_revisits, revisited_caves, ref rest @ ..] => {
+            let policy = VisitPolicy {
+                max_revisits: max_revisits.parse().expect("failed to parse max revisits"),
+                revisited_caves: revisited_caves
+                    .parse()
+                    .expect("failed to parse revisited caves"),
+            };
+            // other endpoints need their own checks, the error for the
+            // usual ones has been reported already
+            let graph = match rest {
+                [start, end] => CaveGraph::with_endpoints(&edges, start, end),
+                _ => graph,
+            };
+            match graph {
+                Ok(graph) => println!("{} paths", graph.count_paths(&policy)),
+                Err(e) if !rest.is_empty() => eprintln!("invalid caves: {}", e),
+                Err(_) => {}
+            }
+        }
+        ["paths", rule, ref rest @ ..] => {
+            // the error has been reported already
+            let graph = match graph {
+                Ok(graph) => graph,
+                Err(_) => return,
+            };
+            let policy = match rule {
+                "2" => VisitPolicy::one_twice(),
+                _ => VisitPolicy::default(),
+            };
+            let mut paths = graph.paths(&policy).sorted();
+            if let Some(max) = rest.first() {
+                paths = paths.max_len(max.parse().expect("failed to parse max caves"));
+            }
+            for path in paths {
+                println!("{}", path.join(","));
+            }
+        }
+        ["dot", ref rest @ ..] => {
+            // the error has been reported already
+            let graph = match graph {
+                Ok(graph) => graph,
+                Err(_) => return,
+            };
+            let path: Vec<&str> = rest.first().map_or(vec![], |p| p.split(',').collect());
+            print!("{}", graph.dot(&path));
+        }
+        _ => {}
+    }
 }
 
 #[test]
@@ -164,7 +526,247 @@ kj-dc"
         .lines()
         .map(|l| l.parse().unwrap())
         .collect();
-    let graph = CaveGraph::new(&edges);
+    let graph = CaveGraph::new(&edges).unwrap();
     assert_eq!(graph.solve1(), 19);
     assert_eq!(graph.solve2(), 103);
+    assert_eq!(graph.paths(&VisitPolicy::default()).count(), 19);
+    assert_eq!(graph.paths(&VisitPolicy::one_twice()).count(), 103);
+}
+
+#[test]
+fn test_list_paths() {
+    let edges: Vec<Edge<String>> = ["start-A", "start-b", "A-c", "A-b", "b-d", "A-end", "b-end"]
+        .iter()
+        .map(|l| l.parse().unwrap())
+        .collect();
+    let graph = CaveGraph::new(&edges).unwrap();
+    let paths: Vec<String> = graph
+        .paths(&VisitPolicy::default())
+        .sorted()
+        .map(|p| p.join(","))
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            "start,A,b,A,c,A,end",
+            "start,A,b,A,end",
+            "start,A,b,end",
+            "start,A,c,A,b,A,end",
+            "start,A,c,A,b,end",
+            "start,A,c,A,end",
+            "start,A,end",
+            "start,b,A,c,A,end",
+            "start,b,A,end",
+            "start,b,end",
+        ]
+    );
+    assert_eq!(graph.paths(&VisitPolicy::default()).count(), graph.solve1());
+    assert_eq!(graph.paths(&VisitPolicy::one_twice()).count(), 36);
+
+    let mut sorted: Vec<Vec<&str>> = graph.paths(&VisitPolicy::one_twice()).sorted().collect();
+    assert!(sorted.windows(2).all(|w| w[0] < w[1]));
+    sorted.sort();
+    let mut unsorted: Vec<Vec<&str>> = graph.paths(&VisitPolicy::one_twice()).collect();
+    unsorted.sort();
+    assert_eq!(sorted, unsorted);
+
+    assert_eq!(
+        graph
+            .paths(&VisitPolicy::one_twice())
+            .sorted()
+            .max_len(3)
+            .collect::<Vec<_>>(),
+        vec![vec!["start", "A", "end"], vec!["start", "b", "end"]]
+    );
+    assert_eq!(graph.paths(&VisitPolicy::one_twice()).max_len(2).count(), 0);
+    assert!(graph
+        .paths(&VisitPolicy::one_twice())
+        .max_len(5)
+        .all(|p| p.len() <= 5));
+}
+
+#[test]
+fn test_many_small_caves() {
+    // a chain of small caves with two big caves between each pair, so there
+    // are 2^links paths through it
+    let chain = |links: usize| {
+        let mut edges = vec!["start-s0".to_string(), format!("s{}-end", links)];
+        for i in 0..links {
+            edges.push(format!("s{}-A{}", i, i));
+            edges.push(format!("A{}-s{}", i, i + 1));
+            edges.push(format!("s{}-B{}", i, i));
+            edges.push(format!("B{}-s{}", i, i + 1));
+        }
+        let edges: Vec<Edge<String>> = edges.iter().map(|l| l.parse().unwrap()).collect();
+        CaveGraph::new(&edges).unwrap()
+    };
+    for links in 1..6 {
+        let graph = chain(links);
+        assert_eq!(graph.solve1(), 1 << links);
+        assert_eq!(graph.solve1(), graph.paths(&VisitPolicy::default()).count());
+        assert_eq!(
+            graph.solve2(),
+            graph.paths(&VisitPolicy::one_twice()).count()
+        );
+    }
+
+    // far too many paths to walk one by one, and 4 * links + 1 times as many
+    // with a revisit
+    let graph = chain(61);
+    assert_eq!(graph.small_bits.iter().flatten().count(), 64);
+    assert_eq!(graph.solve1(), 1 << 61);
+    assert_eq!(chain(40).solve2(), (4 * 40 + 1) << 40);
+}
+
+#[test]
+fn test_visit_policy() {
+    let edges: Vec<Edge<String>> = ["start-A", "start-b", "A-c", "A-b", "b-d", "A-end", "b-end"]
+        .iter()
+        .map(|l| l.parse().unwrap())
+        .collect();
+    let graph = CaveGraph::new(&edges).unwrap();
+    let policies = [
+        VisitPolicy::default(),
+        VisitPolicy::one_twice(),
+        VisitPolicy {
+            max_revisits: 2,
+            revisited_caves: 1,
+        },
+        VisitPolicy {
+            max_revisits: 1,
+            revisited_caves: 2,
+        },
+        VisitPolicy {
+            max_revisits: 2,
+            revisited_caves: 3,
+        },
+    ];
+    // counting and walking the paths agree between any two caves
+    for start in graph.lookup.iter() {
+        for end in graph.lookup.iter().filter(|&end| end != start) {
+            let graph = CaveGraph::with_endpoints(&edges, start, end).unwrap();
+            for policy in policies.iter() {
+                assert_eq!(graph.count_paths(policy), graph.paths(policy).count());
+            }
+        }
+    }
+    assert_eq!(graph.count_paths(&policies[0]), 10);
+    assert_eq!(graph.count_paths(&policies[1]), 36);
+    // no revisits allowed however many caves could have them
+    let policy = VisitPolicy {
+        revisited_caves: 5,
+        ..Default::default()
+    };
+    assert_eq!(graph.count_paths(&policy), 10);
+    let graph = CaveGraph::with_endpoints(&edges, "c", "d").unwrap();
+    assert_eq!(
+        graph.paths(&policies[1]).sorted().next(),
+        Some(vec!["c", "A", "b", "A", "b", "d"])
+    );
+
+    assert_eq!(
+        CaveGraph::with_endpoints(&edges, "start", "exit").err(),
+        Some(GraphError::MissingCave("exit".to_string()))
+    );
+    let err = CaveGraph::with_endpoints(&edges, "b", "b").err().unwrap();
+    assert_eq!(err, GraphError::SameEndpoints("b".to_string()));
+    assert_eq!(err.to_string(), "paths can't both start and end at b");
+
+    let edges: Vec<Edge<String>> = ["start-A", "A-B", "B-end"]
+        .iter()
+        .map(|l| l.parse().unwrap())
+        .collect();
+    let err = CaveGraph::new(&edges).err().unwrap();
+    assert_eq!(
+        err,
+        GraphError::BigNeighbours("A".to_string(), "B".to_string())
+    );
+    assert_eq!(
+        err.to_string(),
+        "big caves A and B are connected, paths through them never end"
+    );
+
+    let edges: Vec<Edge<String>> = (0..65)
+        .map(|i| format!("s{}-X", i).parse().unwrap())
+        .chain(["start-X".parse().unwrap(), "X-end".parse().unwrap()])
+        .collect();
+    assert_eq!(
+        CaveGraph::new(&edges).err(),
+        Some(GraphError::TooManySmallCaves(67))
+    );
+
+    // endpoints and names are checked when the graph is built
+    let edges: Vec<Edge<String>> = ["start-A", "A-b"]
+        .iter()
+        .map(|l| l.parse().unwrap())
+        .collect();
+    assert_eq!(
+        CaveGraph::new(&edges).err(),
+        Some(GraphError::MissingCave("end".to_string()))
+    );
+    let graph = CaveGraph::with_endpoints(&edges, "start", "b").unwrap();
+    assert_eq!(graph.solve1(), 1);
+    assert_eq!(graph.paths(&VisitPolicy::default()).count(), 1);
+
+    let edges: Vec<Edge<String>> = ["start-A", "A-end", "start-"]
+        .iter()
+        .map(|l| l.parse().unwrap())
+        .collect();
+    let err = CaveGraph::new(&edges).err().unwrap();
+    assert_eq!(err, GraphError::UnnamedCave(2));
+    assert_eq!(
+        err.to_string(),
+        "the tunnel on line 3 leads to a cave with no name"
+    );
+}
+
+#[test]
+fn test_dot() {
+    let edges: Vec<Edge<String>> = ["start-A", "start-b", "A-b", "A-end"]
+        .iter()
+        .map(|l| l.parse().unwrap())
+        .collect();
+    let graph = CaveGraph::new(&edges).unwrap();
+    assert_eq!(
+        graph.dot(&["start", "b", "A", "end"]),
+        "graph caves {
+    \"start\" [shape=circle, peripheries=2, color=red, fontcolor=red];
+    \"A\" [shape=box, style=filled, fillcolor=lightgrey, color=red, fontcolor=red];
+    \"b\" [shape=circle, color=red, fontcolor=red];
+    \"end\" [shape=circle, peripheries=2, color=red, fontcolor=red];
+    \"start\" -- \"A\";
+    \"start\" -- \"b\" [color=red, penwidth=2];
+    \"A\" -- \"b\" [color=red, penwidth=2];
+    \"A\" -- \"end\" [color=red, penwidth=2];
+}
+"
+    );
+    let dot = graph.dot(&[]);
+    assert!(!dot.contains("red"));
+    assert_eq!(dot.matches(" -- ").count(), 4);
+}
+
+#[test]
+fn test_large_graph() {
+    // thousands of big caves, each a tunnel between two of a ring of small
+    // caves, with every tunnel also listed the other way around
+    let mut edges = Vec::new();
+    for i in 0..3000 {
+        for (a, b) in [(i % 6, (i + 1) % 6), ((i + 1) % 6, i % 6)] {
+            edges.push(Edge(format!("s{}", a), format!("B{}", i)));
+            edges.push(Edge(format!("B{}", i), format!("s{}", b)));
+        }
+    }
+    edges.push(Edge("start".to_string(), "s0".to_string()));
+    edges.push(Edge("s3".to_string(), "end".to_string()));
+    let graph = CaveGraph::new(&edges).unwrap();
+    assert_eq!(graph.lookup.len(), 3000 + 6 + 2);
+    let cave = |name: &str| graph.lookup.iter().position(|c| c == name).unwrap();
+    let s1 = cave("s1");
+    assert_eq!(graph.neighbors(s1).len(), 1000);
+    let b0 = cave("B0");
+    assert_eq!(graph.neighbors(b0), &[cave("s0"), s1]);
+    // 500 tunnels between each pair of neighbouring small caves, going
+    // either way around the ring
+    assert_eq!(graph.solve1(), 2 * 500usize.pow(3));
 }
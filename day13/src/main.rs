@@ -1,3 +1,5 @@
+mod ocr;
+
 use std::{collections::HashSet, str::FromStr};
 
 #[derive(Hash, PartialEq, Eq)]
@@ -23,7 +25,7 @@ impl FromStr for Fold {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let spec = s.split(' ').nth(2).unwrap();
-        let equals = spec.find(|c| c == '=').unwrap();
+        let equals = spec.find('=').unwrap();
         match &spec[..equals] {
             "x" => Ok(Fold::X(spec[equals + 1..].trim().parse().unwrap())),
             "y" => Ok(Fold::Y(spec[equals + 1..].trim().parse().unwrap())),
@@ -53,7 +55,7 @@ fn plot(points: &HashSet<Point>) {
     for y in 0..=max_y {
         if points.iter().any(|p| p.1 == y) {
             let mut ps: Vec<&Point> = points.iter().filter(|p| p.1 == y).collect();
-            ps.sort_by(|a, b| a.0.cmp(&b.0));
+            ps.sort_by_key(|a| a.0);
             let mut last_x = 0;
             for p in ps {
                 for _ in last_x..p.0 {
@@ -82,6 +84,12 @@ fn main() {
         fold(&mut points, &f);
     }
 
-    println!("solution 2: ");
-    plot(&points);
+    match ocr::read(points.iter().map(|p| (p.0, p.1))) {
+        Ok(letters) => println!("solution 2: {}", letters),
+        Err(e) => {
+            // leave the reading to a human
+            println!("solution 2: ({})", e);
+            plot(&points);
+        }
+    }
 }
@@ -0,0 +1,146 @@
+use std::fmt;
+
+// The letters the puzzles spell out, each four dots wide and six tall with
+// an empty column between letters.
+const GLYPHS: [(char, [&str; 6]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+const WIDTH: usize = 4;
+const HEIGHT: usize = 6;
+
+#[derive(Debug, PartialEq)]
+pub enum OcrError {
+    Empty,
+    Height(usize),
+    UnknownGlyph(usize),
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OcrError::Empty => write!(f, "there are no dots to read"),
+            OcrError::Height(h) => write!(f, "the dots are {} high, letters are {}", h, HEIGHT),
+            OcrError::UnknownGlyph(i) => write!(f, "letter {} isn't in the font", i + 1),
+        }
+    }
+}
+
+// Reads the letters spelled out by the dots. The folded paper always has its
+// corner at the origin, so the first letter starts at x = 0 even when its
+// own first column is blank, like an I.
+pub fn read(dots: impl IntoIterator<Item = (u32, u32)>) -> Result<String, OcrError> {
+    let dots: Vec<(u32, u32)> = dots.into_iter().collect();
+    let width = dots
+        .iter()
+        .map(|d| d.0 as usize + 1)
+        .max()
+        .ok_or(OcrError::Empty)?;
+    let height = dots.iter().map(|d| d.1 as usize + 1).max().unwrap();
+    if height != HEIGHT {
+        return Err(OcrError::Height(height));
+    }
+
+    let letters = width.div_ceil(WIDTH + 1);
+    let mut grid = vec![vec![false; letters * (WIDTH + 1)]; HEIGHT];
+    for (x, y) in dots {
+        grid[y as usize][x as usize] = true;
+    }
+
+    (0..letters)
+        .map(|i| {
+            let left = i * (WIDTH + 1);
+            let blank_gap = grid.iter().all(|row| !row[left + WIDTH]);
+            GLYPHS
+                .iter()
+                .find(|(_, rows)| {
+                    rows.iter().zip(grid.iter()).all(|(glyph, row)| {
+                        glyph
+                            .chars()
+                            .zip(&row[left..left + WIDTH])
+                            .all(|(c, &dot)| (c == '#') == dot)
+                    })
+                })
+                .filter(|_| blank_gap)
+                .map(|(letter, _)| *letter)
+                .ok_or(OcrError::UnknownGlyph(i))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+fn dots(art: &str) -> Vec<(u32, u32)> {
+    let mut dots = Vec::new();
+    for (y, line) in art.lines().enumerate() {
+        for (x, c) in line.chars().enumerate() {
+            if c == '#' {
+                dots.push((x as u32, y as u32));
+            }
+        }
+    }
+    dots
+}
+
+#[test]
+fn test_read() {
+    let art = "\
+#..#..##..#....####.###...##..####.#..#
+#..#.#..#.#.......#.#..#.#..#....#.#..#
+#..#.#....#......#..#..#.#..#...#..#..#
+#..#.#....#.....#...###..####..#...#..#
+#..#.#..#.#....#....#.#..#..#.#....#..#
+.##...##..####.####.#..#.#..#.####..##.";
+    assert_eq!(read(dots(art)), Ok("UCLZRAZU".to_string()));
+
+    // letters with a blank first column still line up
+    assert_eq!(read(dots(&GLYPHS[7].1.join("\n"))), Ok("I".to_string()));
+    let art = "\
+.###..##.
+..#..#..#
+..#..#..#
+..#..#..#
+..#..#..#
+.###..##.";
+    assert_eq!(read(dots(art)), Ok("IO".to_string()));
+
+    // every letter of the font reads back as itself
+    let mut all = Vec::new();
+    for (i, (_, rows)) in GLYPHS.iter().enumerate() {
+        for (x, y) in dots(&rows.join("\n")) {
+            all.push((x + 5 * i as u32, y));
+        }
+    }
+    let letters: String = GLYPHS.iter().map(|(letter, _)| letter).collect();
+    assert_eq!(read(all), Ok(letters));
+
+    assert_eq!(read(vec![]), Err(OcrError::Empty));
+    assert_eq!(read(dots("####\n#..#\n####")), Err(OcrError::Height(3)));
+    let art = "\
+.##..#..#
+#..#.#..#
+#..#.####
+####.#..#
+#..#.#..#
+#..#.#.##";
+    assert_eq!(read(dots(art)), Err(OcrError::UnknownGlyph(1)));
+    assert_eq!(
+        OcrError::UnknownGlyph(1).to_string(),
+        "letter 2 isn't in the font"
+    );
+}
@@ -54,7 +54,7 @@ fn main() {
 
 #[test]
 fn test_small() {
-    let template = vec!['N', 'N', 'C', 'B'];
+    let template = ['N', 'N', 'C', 'B'];
     let last = *template.last().unwrap();
     let mut template: HashMap<(char, char), usize> =
         template.windows(2).map(|w| ((w[0], w[1]), 1)).collect();
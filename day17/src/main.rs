@@ -64,14 +64,14 @@ fn solve2(target: &(RangeInclusive<i32>, RangeInclusive<i32>)) -> i32 {
 }
 
 fn parse_input(s: &str) -> (RangeInclusive<i32>, RangeInclusive<i32>) {
-    let x = s.find(|c| c == 'x').unwrap();
-    let dots = s.find(|c| c == '.').unwrap();
+    let x = s.find('x').unwrap();
+    let dots = s.find('.').unwrap();
     let x_start: i32 = s[x + 2..dots].parse().unwrap();
-    let comma = s.find(|c| c == ',').unwrap();
+    let comma = s.find(',').unwrap();
     let x_end: i32 = s[dots + 2..comma].parse().unwrap();
 
-    let y = s.find(|c| c == 'y').unwrap();
-    let dots = s[dots + 2..].find(|c| c == '.').unwrap() + dots + 2;
+    let y = s.find('y').unwrap();
+    let dots = s[dots + 2..].find('.').unwrap() + dots + 2;
     let y_start: i32 = s[y + 2..dots].parse().unwrap();
     let y_end: i32 = s[dots + 2..].trim_end().parse().unwrap();
 
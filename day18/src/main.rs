@@ -65,7 +65,7 @@ impl Number {
             } else if let Some(i) = self.iter().position(|n| n.inner >= 10) {
                 let num = *self.get(i).unwrap();
                 let left = num.inner / 2;
-                let right = (num.inner + 1) / 2;
+                let right = num.inner.div_ceil(2);
                 self[i] = Num {
                     inner: left,
                     level: num.level + 1,
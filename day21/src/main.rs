@@ -7,7 +7,7 @@ fn main() {
             l.unwrap()
                 .trim_end()
                 .split(' ')
-                .last()
+                .next_back()
                 .unwrap()
                 .parse()
                 .unwrap()
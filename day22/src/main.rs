@@ -59,7 +59,7 @@ impl FromStr for Cuboid {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut iter = s.split(|c| matches!(c, ' ' | ','));
+        let mut iter = s.split([' ', ',']);
         let command = match iter.next().unwrap() {
             "on" => true,
             "off" => false,
@@ -69,7 +69,7 @@ impl FromStr for Cuboid {
         let mut xyz: [RangeInclusive<i32>; 3] = [DEF; 3];
         for r in xyz.iter_mut() {
             let s = iter.next().unwrap();
-            let dot = s.find(|c| c == '.').unwrap();
+            let dot = s.find('.').unwrap();
             let start = s[2..dot].parse().unwrap();
             let end = s[dot + 2..].parse().unwrap();
 
@@ -18,7 +18,7 @@ fn solve1(nums: &[String]) -> u32 {
                 _ => (),
             }
         }
-        let zeros = nums.len() as i32 - one_counter as i32;
+        let zeros = nums.len() as i32 - one_counter;
         if one_counter > zeros {
             gamma |= 1 << bitpos;
         }
@@ -55,7 +55,7 @@ fn solve2(nums: &[String]) -> u32 {
                 _ => (),
             }
         }
-        let zeros = onums.len() as i32 - one_counter as i32;
+        let zeros = onums.len() as i32 - one_counter;
         if one_counter >= zeros {
             onums.retain(|n| n.chars().nth(bitpos).unwrap() == '1');
         } else {
@@ -77,7 +77,7 @@ fn solve2(nums: &[String]) -> u32 {
                 _ => (),
             }
         }
-        let zeros = conums.len() as i32 - one_counter as i32;
+        let zeros = conums.len() as i32 - one_counter;
         if one_counter >= zeros {
             conums.retain(|n| n.chars().nth(bitpos).unwrap() == '0');
         } else {
@@ -1,14 +1,21 @@
-use std::{mem::swap, str::FromStr};
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write},
+    mem::swap,
+    str::FromStr,
+};
 
 #[derive(Debug, Default, PartialEq, Clone, Copy)]
 struct Line {
-    start: (usize, usize),
-    end: (usize, usize),
+    start: (i64, i64),
+    end: (i64, i64),
 }
 
 impl Line {
     #[cfg(test)]
-    pub fn new(x1: usize, y1: usize, x2: usize, y2: usize) -> Self {
+    pub fn new(x1: i64, y1: i64, x2: i64, y2: i64) -> Self {
         let mut line = Line {
             start: (x1, y1),
             end: (x2, y2),
@@ -30,28 +37,76 @@ impl Line {
     pub const fn is_horiz(&self) -> bool {
         self.start.1 == self.end.1
     }
-    pub fn covers(&self, point: (usize, usize)) -> bool {
-        if self.is_hv() {
-            self.start.0 <= point.0
-                && point.0 <= self.end.0
-                && self.start.1 <= point.1
-                && point.1 <= self.end.1
+    // The smallest integer step between two lattice points on the line, and
+    // the number of such steps from start to end.
+    fn step(&self) -> ((i64, i64), i64) {
+        let (dx, dy) = (self.end.0 - self.start.0, self.end.1 - self.start.1);
+        let n = gcd(dx, dy);
+        if n == 0 {
+            ((0, 0), 0)
         } else {
-            self.start <= point && point <= self.end && check(self.start, point, self.end)
+            ((dx / n, dy / n), n)
         }
     }
+    pub fn points(&self) -> Points {
+        let (step, steps) = self.step();
+        Points {
+            next: self.start,
+            step,
+            remaining: steps as usize + 1,
+        }
+    }
+    #[cfg(test)]
+    pub fn covers(&self, point: (i64, i64)) -> bool {
+        let (dx, dy) = (self.end.0 - self.start.0, self.end.1 - self.start.1);
+        let (px, py) = (point.0 - self.start.0, point.1 - self.start.1);
+        // an integer point on the line is always a whole number of steps
+        // away from the start, so being on the segment is enough
+        dx * py == dy * px
+            && self.start.0.min(self.end.0) <= point.0
+            && point.0 <= self.start.0.max(self.end.0)
+            && self.start.1.min(self.end.1) <= point.1
+            && point.1 <= self.start.1.max(self.end.1)
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a as i64
+}
+
+struct Points {
+    next: (i64, i64),
+    step: (i64, i64),
+    remaining: usize,
 }
 
-fn check(a: (usize, usize), b: (usize, usize), c: (usize, usize)) -> bool {
-    let (ax, ay) = (a.0 as i32, a.1 as i32);
-    let (bx, by) = (b.0 as i32, b.1 as i32);
-    let (cx, cy) = (c.0 as i32, c.1 as i32);
-    // cross product
-    ((cy - ay) * (bx - ax) - (cx - ax) * (by - ay)) == 0
-    // dot product
-    && ((cx - ax) * (bx - ax) + (cy - ay) * (by - ay)) >= 0
+impl Iterator for Points {
+    type Item = (i64, i64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let point = self.next;
+        self.remaining -= 1;
+        // the point past the end may not fit in an i64
+        if self.remaining > 0 {
+            self.next = (point.0 + self.step.0, point.1 + self.step.1);
+        }
+        Some(point)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
 }
 
+impl ExactSizeIterator for Points {}
+
 impl FromStr for Line {
     type Err = &'static str;
 
@@ -75,47 +130,132 @@ impl FromStr for Line {
             .parse()
             .map_err(|_| "Failed to parse number")?;
 
+        // the steps along the line have to fit in an i64
+        let fits = |a: i64, b: i64| b.checked_sub(a).is_some_and(|d| d != i64::MIN);
+        if !fits(line.start.0, line.end.0) || !fits(line.start.1, line.end.1) {
+            return Err("Line is too long");
+        }
+
         line.normalize();
         Ok(line)
     }
 }
 
 fn main() {
-    let mut lines: Vec<Line> = input::read_lines("puzzles/day5.txt");
-    lines.iter_mut().for_each(|l| l.normalize());
-
-    let mut points_covered = 0;
-    for x in 0..1000 {
-        for y in 0..1000 {
-            let mut covered_by = 0;
-            for line in lines.iter().filter(|l| l.is_hv()) {
-                if line.covers((x, y)) {
-                    covered_by += 1;
-                    if covered_by == 2 {
-                        points_covered += 1;
-                        break;
-                    }
-                }
+    let lines: Vec<Line> = input::read_lines("puzzles/day5.txt");
+
+    println!(
+        "solution 1: {}",
+        count_overlaps(lines.iter().filter(|l| l.is_hv()))
+    );
+    println!("solution 2: {}", count_overlaps(&lines));
+
+    // `day5 <path>` also writes the full overlap map as a PGM image,
+    // `day5 --ascii` prints it to the terminal instead
+    if let Some(arg) = std::env::args().nth(1) {
+        let heatmap = match Heatmap::new(&coverage(&lines)) {
+            Ok(heatmap) => heatmap,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
             }
+        };
+        if arg == "--ascii" {
+            print!("{}", heatmap);
+        } else {
+            let mut out = BufWriter::new(File::create(&arg).expect("failed to create file"));
+            heatmap.write_pgm(&mut out).expect("failed to write image");
+            println!(
+                "wrote {}x{} heatmap to {}",
+                heatmap.width(),
+                heatmap.height(),
+                arg
+            );
         }
     }
-    println!("solution 1: {}", points_covered);
-    let mut points_covered = 0;
-    for x in 0..1000 {
-        for y in 0..1000 {
-            let mut covered_by = 0;
-            for line in lines.iter() {
-                if line.covers((x, y)) {
-                    covered_by += 1;
-                    if covered_by == 2 {
-                        points_covered += 1;
-                        break;
-                    }
+}
+
+// Walks every line's points into a sparse map, so the cost is proportional
+// to the total length of the lines instead of the area of the map.
+fn coverage<'a>(lines: impl IntoIterator<Item = &'a Line>) -> HashMap<(i64, i64), usize> {
+    let mut covered = HashMap::new();
+    for line in lines {
+        for point in line.points() {
+            *covered.entry(point).or_insert(0) += 1;
+        }
+    }
+    covered
+}
+
+const MAX_HEATMAP_CELLS: usize = 1 << 28;
+
+// Overlap counts laid out over the bounding box of the lines, row by row.
+struct Heatmap {
+    width: usize,
+    counts: Vec<usize>,
+}
+
+impl Heatmap {
+    // Fails for lines so far apart that the box would take more than
+    // `MAX_HEATMAP_CELLS` cells.
+    pub fn new(coverage: &HashMap<(i64, i64), usize>) -> Result<Self, &'static str> {
+        let min_x = coverage.keys().map(|p| p.0).min().unwrap_or(0);
+        let max_x = coverage.keys().map(|p| p.0).max().unwrap_or(-1);
+        let min_y = coverage.keys().map(|p| p.1).min().unwrap_or(0);
+        let max_y = coverage.keys().map(|p| p.1).max().unwrap_or(-1);
+        let span = |min: i64, max: i64| {
+            max.checked_sub(min)
+                .and_then(|d| d.checked_add(1))
+                .and_then(|d| usize::try_from(d).ok())
+        };
+        let (width, height) = match (span(min_x, max_x), span(min_y, max_y)) {
+            (Some(width), Some(height)) => (width, height),
+            _ => return Err("heatmap is too large"),
+        };
+        match width.checked_mul(height) {
+            Some(cells) if cells <= MAX_HEATMAP_CELLS => {}
+            _ => return Err("heatmap is too large"),
+        }
+
+        let mut counts = vec![0; width * height];
+        for (&(x, y), &n) in coverage {
+            counts[(y - min_y) as usize * width + (x - min_x) as usize] = n;
+        }
+        Ok(Heatmap { width, counts })
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.counts.len().checked_div(self.width).unwrap_or(0)
+    }
+    // Binary greyscale PGM, with the most overlapped point in white.
+    pub fn write_pgm<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let max = self.counts.iter().copied().max().unwrap_or(0).max(1);
+        writeln!(out, "P5\n{} {}\n255", self.width, self.height())?;
+        let pixels: Vec<u8> = self.counts.iter().map(|&n| (n * 255 / max) as u8).collect();
+        out.write_all(&pixels)
+    }
+}
+
+impl fmt::Display for Heatmap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.counts.chunks(self.width.max(1)) {
+            for &n in row {
+                match n {
+                    0 => write!(f, ".")?,
+                    1..=9 => write!(f, "{}", n)?,
+                    _ => write!(f, "#")?,
                 }
             }
+            writeln!(f)?;
         }
+        Ok(())
     }
-    println!("solution 2: {}", points_covered);
+}
+
+fn count_overlaps<'a>(lines: impl IntoIterator<Item = &'a Line>) -> usize {
+    coverage(lines).values().filter(|&&n| n >= 2).count()
 }
 
 #[cfg(test)]
@@ -157,7 +297,82 @@ mod test {
             Line::new(0, 0, 8, 8),
             Line::new(5, 5, 8, 2),
         ];
-        assert_eq!(plot(lines), 12);
+        assert_eq!(plot(lines.clone()), 12);
+        assert_eq!(count_overlaps(lines.iter().filter(|l| l.is_hv())), 5);
+        assert_eq!(count_overlaps(&lines), 12);
+
+        let heatmap = Heatmap::new(&coverage(&lines)).unwrap();
+        assert_eq!((heatmap.width(), heatmap.height()), (10, 10));
+        assert_eq!(
+            heatmap.to_string(),
+            "1.1....11.\n\
+             .111...2..\n\
+             ..2.1.111.\n\
+             ...1.2.2..\n\
+             .112313211\n\
+             ...1.2....\n\
+             ..1...1...\n\
+             .1.....1..\n\
+             1.......1.\n\
+             222111....\n"
+        );
+
+        let mut pgm = Vec::new();
+        heatmap.write_pgm(&mut pgm).unwrap();
+        assert!(pgm.starts_with(b"P5\n10 10\n255\n"));
+        assert_eq!(pgm.len(), 13 + 100);
+        assert_eq!(pgm[13 + 4 * 10 + 4], 255);
+    }
+
+    #[test]
+    fn test_any_slope() {
+        let line = Line::from_str("-2,-1 -> 4,2").unwrap();
+        assert_eq!(
+            line.points().collect::<Vec<_>>(),
+            vec![(-2, -1), (0, 0), (2, 1), (4, 2)]
+        );
+        assert!(line.covers((0, 0)));
+        assert!(!line.covers((1, 0)));
+        assert!(!line.covers((6, 3)));
+
+        let line = Line::new(0, 0, 3, 7);
+        assert_eq!(line.points().len(), 2);
+        assert_eq!(Line::new(5, 5, 5, 5).points().len(), 1);
+
+        let lines = vec![
+            Line::new(-2, -1, 4, 2),
+            Line::new(0, -3, 0, 3),
+            Line::new(-3, 3, 3, -3),
+        ];
+        assert_eq!(count_overlaps(&lines), 1);
+
+        // endpoints further apart than an i64 can hold are rejected
+        assert!(Line::from_str("0,0 -> 0,-9223372036854775808").is_err());
+        assert!(Line::from_str("-1,0 -> 9223372036854775807,0").is_err());
+        assert!(Line::from_str("-9223372036854775808,0 -> 9223372036854775807,0").is_err());
+        let line = Line::from_str("0,0 -> 9223372036854775807,9223372036854775807").unwrap();
+        assert_eq!(line.points().len(), i64::MAX as usize + 1);
+        let line = Line::from_str("9223372036854775806,1 -> 9223372036854775807,0").unwrap();
+        assert_eq!(
+            line.points().collect::<Vec<_>>(),
+            vec![(i64::MAX - 1, 1), (i64::MAX, 0)]
+        );
+        let line = Line::from_str("-4611686018427387904,0 -> 4611686018427387903,0").unwrap();
+        assert_eq!(line.points().len(), 1 << 63);
+
+        // far apart lines are too big to lay out
+        let lines = vec![
+            Line::new(0, 0, 1, 1),
+            Line::new(1 << 40, 1 << 40, (1 << 40) + 1, (1 << 40) + 1),
+        ];
+        assert!(Heatmap::new(&coverage(&lines)).is_err());
+        let lines = vec![
+            Line::new(i64::MIN, 0, i64::MIN, 0),
+            Line::new(i64::MAX, 0, i64::MAX, 0),
+        ];
+        assert!(Heatmap::new(&coverage(&lines)).is_err());
+        let lines = vec![Line::new(0, 0, 0, 0), Line::new(i64::MAX, 0, i64::MAX, 0)];
+        assert!(Heatmap::new(&coverage(&lines)).is_err());
     }
 
     fn plot(lines: Vec<Line>) -> usize {
@@ -1,29 +1,278 @@
 fn main() {
     let input: Vec<usize> = input::read_line_split("puzzles/day6.txt", ",");
+    let cycle = LifeCycle::default();
 
-    println!("solution 1: {}", solve(&input, 80));
-    println!("solution 2: {}", solve(&input, 256));
+    println!("solution 1: {}", solve(&input, 80, cycle));
+    println!("solution 2: {}", project_exact(&input, 256, cycle).unwrap());
+
+    // `day6 <days> [modulus]` projects the population further out,
+    // `day6 history <days> [reset newborn]` prints the per-timer counts of
+    // every day as CSV
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.iter().map(|s| s.as_str()).collect::<Vec<_>>()[..] {
+        ["history", days, ..] => {
+            let days = days.parse().expect("failed to parse days");
+            let cycle = match args[2..] {
+                [ref reset, ref newborn] => LifeCycle {
+                    reset: reset.parse().expect("failed to parse reset timer"),
+                    newborn: newborn.parse().expect("failed to parse newborn timer"),
+                },
+                _ => cycle,
+            };
+            let timers: Vec<String> = (0..cycle.buckets()).map(|t| format!("t{}", t)).collect();
+            println!("day,{},total", timers.join(","));
+            for (day, fish) in history(&input, days, cycle).iter().enumerate() {
+                let counts: Vec<String> = fish.iter().map(|n| n.to_string()).collect();
+                println!(
+                    "{},{},{}",
+                    day,
+                    counts.join(","),
+                    fish.iter().sum::<usize>()
+                );
+            }
+        }
+        [days] => {
+            let days: u64 = days.parse().expect("failed to parse days");
+            match project_exact(&input, days, cycle) {
+                Some(total) => println!("after {} days: {}", days, total),
+                None => println!("after {} days: too many fish, pass a modulus", days),
+            }
+        }
+        [days, modulus] => {
+            let days: u64 = days.parse().expect("failed to parse days");
+            let modulus = modulus.parse().expect("failed to parse modulus");
+            match project_mod(&input, days, modulus, cycle) {
+                Some(total) => println!("after {} days: {} (mod {})", days, total, modulus),
+                None => println!("after {} days: the modulus has to be at least 1", days),
+            }
+        }
+        _ => {}
+    }
 }
 
-fn solve(start: &[usize], days: u32) -> usize {
-    let mut fish_spawners = [0; 9];
+// The timer a fish goes back to after spawning, and the timer a newborn
+// fish starts with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LifeCycle {
+    reset: usize,
+    newborn: usize,
+}
 
+impl Default for LifeCycle {
+    fn default() -> Self {
+        LifeCycle {
+            reset: 6,
+            newborn: 8,
+        }
+    }
+}
+
+impl LifeCycle {
+    pub fn buckets(&self) -> usize {
+        self.reset.max(self.newborn) + 1
+    }
+}
+
+fn spawners(start: &[usize], cycle: LifeCycle) -> Vec<usize> {
+    let mut fish_spawners = vec![0; cycle.buckets()];
     for fish in start {
+        assert!(
+            *fish < fish_spawners.len(),
+            "timer {} is past the life cycle",
+            fish
+        );
         fish_spawners[*fish] += 1;
     }
+    fish_spawners
+}
+
+fn step(fish_spawners: &mut [usize], cycle: LifeCycle) {
+    let spawning = fish_spawners[0];
+    fish_spawners.rotate_left(1);
+    *fish_spawners.last_mut().unwrap() = 0;
+    fish_spawners[cycle.reset] += spawning;
+    fish_spawners[cycle.newborn] += spawning;
+}
+
+fn solve(start: &[usize], days: u32, cycle: LifeCycle) -> usize {
+    let mut fish_spawners = spawners(start, cycle);
 
     for _ in 1..=days {
-        fish_spawners.rotate_left(1);
-        fish_spawners[6] += fish_spawners[8];
+        step(&mut fish_spawners, cycle);
     }
 
     fish_spawners.iter().sum()
 }
 
+// The count of fish in each timer bucket, from the starting day up to and
+// including `days`.
+fn history(start: &[usize], days: u32, cycle: LifeCycle) -> Vec<Vec<usize>> {
+    let mut fish_spawners = spawners(start, cycle);
+    let mut days_seen = vec![fish_spawners.clone()];
+
+    for _ in 1..=days {
+        step(&mut fish_spawners, cycle);
+        days_seen.push(fish_spawners.clone());
+    }
+
+    days_seen
+}
+
+type Matrix = Vec<Vec<u128>>;
+
+fn identity(n: usize) -> Matrix {
+    let mut m = vec![vec![0; n]; n];
+    for (i, row) in m.iter_mut().enumerate() {
+        row[i] = 1;
+    }
+    m
+}
+
+// One day of the simulation as a linear map on the timer buckets:
+// every timer moves down by one, timer 0 resets and spawns a newborn.
+fn transition(cycle: LifeCycle) -> Matrix {
+    let n = cycle.buckets();
+    let mut m = vec![vec![0; n]; n];
+    for timer in 1..n {
+        m[timer - 1][timer] = 1;
+    }
+    m[cycle.reset][0] += 1;
+    m[cycle.newborn][0] += 1;
+    m
+}
+
+// With a modulus every entry is reduced as it's computed, without one any
+// overflow makes the whole product `None`.
+fn mat_mul(a: &Matrix, b: &Matrix, modulus: Option<u128>) -> Option<Matrix> {
+    let n = a.len();
+    let mut c = vec![vec![0; n]; n];
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                c[i][j] = match modulus {
+                    Some(m) => (c[i][j] + a[i][k] * b[k][j] % m) % m,
+                    None => c[i][j].checked_add(a[i][k].checked_mul(b[k][j])?)?,
+                };
+            }
+        }
+    }
+    Some(c)
+}
+
+fn project(start: &[usize], days: u64, modulus: Option<u128>, cycle: LifeCycle) -> Option<u128> {
+    let mut result = identity(cycle.buckets());
+    let mut power = transition(cycle);
+    let mut days = days;
+    while days > 0 {
+        if days & 1 == 1 {
+            result = mat_mul(&power, &result, modulus)?;
+        }
+        days >>= 1;
+        if days > 0 {
+            power = mat_mul(&power, &power, modulus)?;
+        }
+    }
+
+    let mut total: u128 = 0;
+    for fish in start {
+        for row in result.iter() {
+            total = match modulus {
+                Some(m) => (total + row[*fish]) % m,
+                None => total.checked_add(row[*fish])?,
+            };
+        }
+    }
+    Some(total)
+}
+
+// Exact population after `days`, or `None` once it no longer fits in a u128
+// (just under 1000 days for the example school).
+fn project_exact(start: &[usize], days: u64, cycle: LifeCycle) -> Option<u128> {
+    project(start, days, None, cycle)
+}
+
+// Population after `days` modulo `modulus`, which works for any number of
+// days, or `None` for a modulus of 0. The modulus has to fit in a u64 so
+// products can't overflow.
+fn project_mod(start: &[usize], days: u64, modulus: u64, cycle: LifeCycle) -> Option<u64> {
+    if modulus == 0 {
+        return None;
+    }
+    Some(project(start, days, Some(modulus as u128), cycle).unwrap() as u64)
+}
+
 #[test]
 fn test() {
     let days = &[3, 4, 3, 1, 2];
-    assert_eq!(solve(days, 18), 26);
-    assert_eq!(solve(days, 80), 5934);
-    assert_eq!(solve(days, 256), 26984457539);
+    let cycle = LifeCycle::default();
+    assert_eq!(solve(days, 18, cycle), 26);
+    assert_eq!(solve(days, 80, cycle), 5934);
+    assert_eq!(solve(days, 256, cycle), 26984457539);
+}
+
+#[test]
+fn test_project() {
+    let days = &[3, 4, 3, 1, 2];
+    let cycle = LifeCycle::default();
+    assert_eq!(project_exact(days, 0, cycle), Some(5));
+    assert_eq!(project_exact(days, 18, cycle), Some(26));
+    assert_eq!(project_exact(days, 80, cycle), Some(5934));
+    assert_eq!(project_exact(days, 256, cycle), Some(26984457539));
+    assert_eq!(
+        project_exact(days, 400, cycle),
+        Some(solve(days, 400, cycle) as u128)
+    );
+    assert_eq!(project_exact(days, 1_000_000, cycle), None);
+
+    assert_eq!(
+        project_mod(days, 256, 1_000_000_007, cycle),
+        Some(26984457539 % 1_000_000_007)
+    );
+    assert_eq!(project_mod(days, 10_000, 1 << 40, cycle), {
+        // the same projection, one day at a time
+        let mut fish = [0u64, 1, 1, 2, 1, 0, 0, 0, 0];
+        for _ in 0..10_000 {
+            fish.rotate_left(1);
+            fish[6] = (fish[6] + fish[8]) % (1 << 40);
+        }
+        Some(fish.iter().sum::<u64>() % (1 << 40))
+    });
+    assert!(project_mod(days, 1_000_000_000_000, 1_000_000_007, cycle).unwrap() < 1_000_000_007);
+    assert_eq!(project_mod(days, 100, 1, cycle), Some(0));
+    assert_eq!(project_mod(days, 100, 0, cycle), None);
+}
+
+#[test]
+fn test_life_cycle() {
+    let days = &[3, 4, 3, 1, 2];
+    let history = history(days, 3, LifeCycle::default());
+    assert_eq!(history.len(), 4);
+    assert_eq!(history[0], vec![0, 1, 1, 2, 1, 0, 0, 0, 0]);
+    assert_eq!(history[2], vec![1, 2, 1, 0, 0, 0, 1, 0, 1]);
+    assert_eq!(history[3].iter().sum::<usize>(), 7);
+
+    // every fish spawns every other day, newborns wait one extra day
+    let cycle = LifeCycle {
+        reset: 1,
+        newborn: 2,
+    };
+    assert_eq!(cycle.buckets(), 3);
+    assert_eq!(solve(&[0], 1, cycle), 2);
+    assert_eq!(solve(&[0], 4, cycle), 4);
+    for days_out in 0..50 {
+        assert_eq!(
+            project_exact(&[0, 1, 2], days_out, cycle),
+            Some(solve(&[0, 1, 2], days_out as u32, cycle) as u128)
+        );
+    }
+
+    // a newborn timer below the reset timer works too
+    let cycle = LifeCycle {
+        reset: 4,
+        newborn: 2,
+    };
+    assert_eq!(
+        project_exact(days, 100, cycle),
+        Some(solve(days, 100, cycle) as u128)
+    );
 }
@@ -1,34 +1,265 @@
 fn main() {
     let input: Vec<u32> = input::read_line_split("puzzles/day7.txt", ",");
-    println!("solution 1: {}", solve1(&input));
-    println!("solution 2: {}", solve2(&input));
+    let (pos, cost) = solve1(&input);
+    println!("solution 1: {} (position {})", cost, pos);
+    let (pos, cost) = solve2(&input);
+    println!("solution 2: {} (position {})", cost, pos);
+
+    // `day7 <model> [param]` aligns the crabs with a different cost model
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (pos, cost) = match args.iter().map(|s| s.as_str()).collect::<Vec<_>>()[..] {
+        [] => return,
+        ["linear"] => minimise(&input, &Linear),
+        ["triangular"] => minimise(&input, &Triangular),
+        ["quadratic"] => minimise(&input, &Quadratic),
+        ["capped", cap] => minimise(
+            &input,
+            &Capped {
+                inner: Triangular,
+                cap: cap.parse().expect("failed to parse cap"),
+            },
+        ),
+        ["weighted", weights] => minimise(
+            &input,
+            &Weighted::new(
+                weights
+                    .split(',')
+                    .map(|w| w.parse().expect("failed to parse weight"))
+                    .collect(),
+            ),
+        ),
+        _ => panic!("usage: day7 [linear|triangular|quadratic|capped <cap>|weighted <w1,w2,..>]"),
+    };
+    println!("{}: {} (position {})", args[0], cost, pos);
+}
+
+// The fuel one crab burns to move a given distance.
+trait FuelCost {
+    fn cost(&self, dist: u32) -> usize;
+
+    // Whether the total cost over all crabs is convex in the target, which
+    // holds whenever `cost` itself is convex. Non-convex costs fall back to
+    // trying every target.
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+struct Linear;
+
+impl FuelCost for Linear {
+    fn cost(&self, dist: u32) -> usize {
+        dist as usize
+    }
+}
+
+struct Triangular;
+
+impl FuelCost for Triangular {
+    fn cost(&self, dist: u32) -> usize {
+        let dist = dist as usize;
+        ((1 + dist) * dist) / 2
+    }
+}
+
+struct Quadratic;
+
+impl FuelCost for Quadratic {
+    fn cost(&self, dist: u32) -> usize {
+        let dist = dist as usize;
+        dist * dist
+    }
+}
+
+// Any crab that would need more than `cap` fuel gets towed for `cap`.
+struct Capped<C> {
+    inner: C,
+    cap: usize,
+}
+
+impl<C: FuelCost> FuelCost for Capped<C> {
+    fn cost(&self, dist: u32) -> usize {
+        self.inner.cost(dist).min(self.cap)
+    }
+    fn is_convex(&self) -> bool {
+        false
+    }
+}
+
+// Every step of the move costs its own weight, steps past the end of the
+// table cost as much as the last one.
+struct Weighted {
+    weights: Vec<usize>,
+}
+
+impl Weighted {
+    pub fn new(weights: Vec<usize>) -> Self {
+        assert!(
+            !weights.is_empty(),
+            "weighted cost needs at least one weight"
+        );
+        Weighted { weights }
+    }
+}
+
+impl FuelCost for Weighted {
+    fn cost(&self, dist: u32) -> usize {
+        let dist = dist as usize;
+        let last = *self.weights.last().unwrap();
+        let within = dist.min(self.weights.len());
+        self.weights[..within].iter().sum::<usize>() + (dist - within) * last
+    }
+    fn is_convex(&self) -> bool {
+        self.weights.windows(2).all(|w| w[0] <= w[1])
+    }
+}
+
+// Plain closures work as non-convex costs.
+impl<F: Fn(u32) -> usize> FuelCost for F {
+    fn cost(&self, dist: u32) -> usize {
+        self(dist)
+    }
+    fn is_convex(&self) -> bool {
+        false
+    }
+}
+
+fn total<C: FuelCost + ?Sized>(input: &[u32], target: u32, cost: &C) -> usize {
+    input
+        .iter()
+        .map(|pos| cost.cost(pos.abs_diff(target)))
+        .sum()
 }
 
-fn solve1(input: &[u32]) -> usize {
-    let max_val = *input.iter().max().unwrap();
-    let mut lowest = usize::MAX;
+// The cheapest target for any cost model, searched between the outermost
+// crabs. Convex costs use a ternary search, anything else tries every target.
+fn minimise<C: FuelCost + ?Sized>(input: &[u32], cost: &C) -> (u32, usize) {
+    let mut lo = *input.iter().min().unwrap();
+    let mut hi = *input.iter().max().unwrap();
 
-    for target in 0..max_val {
-        let mut cost = 0;
-        for pos in input {
-            cost += (*pos as i32 - target as i32).abs() as usize;
+    if cost.is_convex() {
+        while hi - lo > 2 {
+            let m1 = lo + (hi - lo) / 3;
+            let m2 = hi - (hi - lo) / 3;
+            if total(input, m1, cost) <= total(input, m2, cost) {
+                hi = m2;
+            } else {
+                lo = m1;
+            }
         }
-        lowest = cost.min(lowest);
     }
-    lowest
+
+    (lo..=hi)
+        .map(|target| (target, total(input, target, cost)))
+        .min_by_key(|&(_, cost)| cost)
+        .unwrap()
+}
+
+// The sum of distances is minimised at the median.
+fn solve1(input: &[u32]) -> (u32, usize) {
+    let mut sorted = input.to_vec();
+    sorted.sort_unstable();
+    let median = sorted[sorted.len() / 2];
+    (median, total(input, median, &Linear))
 }
 
-fn solve2(input: &[u32]) -> usize {
-    let max_val = *input.iter().max().unwrap();
-    let mut lowest = usize::MAX;
+// Each crab's cost d(d+1)/2 is convex in the target, and so is their sum.
+// On a convex sequence the differences between neighbours never decrease,
+// so a target that's no worse than either neighbour can't have anything
+// cheaper further out on either side: checking the two neighbours is enough
+// to prove it's the global minimum. Relaxed to real targets the slope is
+// n(t - mean) plus at most n/2 from the distance terms, so the minimum is
+// within half a step of the mean and the walk from the rounded mean only
+// takes a step or two.
+fn solve2(input: &[u32]) -> (u32, usize) {
+    let sum: u64 = input.iter().map(|&pos| pos as u64).sum();
+    let len = input.len() as u64;
+    let mut target = ((sum + len / 2) / len) as u32;
+    let mut cost = total(input, target, &Triangular);
 
-    for target in 0..max_val {
-        let mut cost = 0;
-        for pos in input {
-            let dist = (*pos as i32 - target as i32).abs() as usize;
-            cost += ((1 + dist) * dist) / 2;
+    loop {
+        if target > 0 {
+            let left = total(input, target - 1, &Triangular);
+            if left < cost {
+                target -= 1;
+                cost = left;
+                continue;
+            }
         }
-        lowest = cost.min(lowest);
+        let right = total(input, target + 1, &Triangular);
+        if right < cost {
+            target += 1;
+            cost = right;
+            continue;
+        }
+        return (target, cost);
     }
-    lowest
+}
+
+#[test]
+fn test() {
+    let input = &[16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+    assert_eq!(solve1(input), (2, 37));
+    assert_eq!(solve2(input), (5, 168));
+
+    let input = &[0, 0, 0, 4_000_000];
+    assert_eq!(solve1(input), (0, 4_000_000));
+    let (target, cost) = solve2(input);
+    assert_eq!(target, 1_000_000);
+    assert!(cost <= total(input, 999_999, &Triangular));
+    assert!(cost <= total(input, 1_000_001, &Triangular));
+
+    assert_eq!(solve1(&[7]), (7, 0));
+    assert_eq!(solve2(&[7]), (7, 0));
+}
+
+#[test]
+fn test_optimal() {
+    // the median and the neighbour check against every target
+    let mut seed = 7u64;
+    for len in 1..40 {
+        let input: Vec<u32> = (0..len)
+            .map(|_| {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (seed >> 33) as u32 % 500
+            })
+            .collect();
+        let max = *input.iter().max().unwrap();
+        let best = |cost: &dyn FuelCost| {
+            (0..=max)
+                .map(|target| total(&input, target, cost))
+                .min()
+                .unwrap()
+        };
+        assert_eq!(solve1(&input).1, best(&Linear));
+        assert_eq!(solve2(&input).1, best(&Triangular));
+    }
+}
+
+#[test]
+fn test_cost_models() {
+    let input = &[16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+    assert_eq!(minimise(input, &Linear).1, 37);
+    assert_eq!(minimise(input, &Triangular), (5, 168));
+    assert_eq!(minimise(input, &Quadratic), (5, 291));
+
+    // the triangular cost is the same as stepping with weights 1, 2, 3, ..
+    let steps = Weighted::new((1..=20).collect());
+    assert!(steps.is_convex());
+    assert_eq!(minimise(input, &steps), (5, 168));
+    assert_eq!(Weighted::new(vec![1, 3]).cost(4), 1 + 3 + 3 + 3);
+    assert!(!Weighted::new(vec![3, 1]).is_convex());
+
+    // towing the two far crabs makes it cheaper to stay with the rest
+    let capped = Capped {
+        inner: Triangular,
+        cap: 20,
+    };
+    assert_eq!(minimise(input, &capped), (2, 63));
+
+    // closures are searched exhaustively
+    let odd = |dist: u32| (dist % 2) as usize;
+    assert_eq!(minimise(&[0, 2, 4, 7], &odd), (0, 1));
 }
@@ -1,69 +1,123 @@
-use std::{fmt::Write, str::FromStr};
+use std::{fmt, ops, str::FromStr};
+
+// A set of wires or segments, bit i standing for letter `a` + i.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Segments(u8);
+
+impl Segments {
+    pub const fn single(idx: usize) -> Self {
+        Segments(1 << idx)
+    }
+    pub const fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+    pub const fn contains(self, idx: usize) -> bool {
+        self.0 & (1 << idx) != 0
+    }
+    pub const fn is_subset(self, other: Segments) -> bool {
+        self.0 & other.0 == self.0
+    }
+    pub fn iter(self) -> impl Iterator<Item = usize> {
+        (0..SEGMENTS).filter(move |&idx| self.contains(idx))
+    }
+}
+
+impl ops::BitOr for Segments {
+    type Output = Segments;
+
+    fn bitor(self, rhs: Segments) -> Segments {
+        Segments(self.0 | rhs.0)
+    }
+}
+
+impl ops::BitAnd for Segments {
+    type Output = Segments;
+
+    fn bitand(self, rhs: Segments) -> Segments {
+        Segments(self.0 & rhs.0)
+    }
+}
+
+impl FromStr for Segments {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Segments::default();
+        for c in s.trim().bytes() {
+            if !(b'a'..=b'g').contains(&c) {
+                return Err("Invalid segment");
+            }
+            segments = segments | Segments::single((c - b'a') as usize);
+        }
+        Ok(segments)
+    }
+}
+
+impl fmt::Display for Segments {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for idx in self.iter() {
+            write!(f, "{}", (b'a' + idx as u8) as char)?;
+        }
+        Ok(())
+    }
+}
 
 struct Entry {
-    patterns: [Vec<char>; 10],
-    codes: [Vec<char>; 4],
+    patterns: [Segments; 10],
+    codes: [Segments; 4],
 }
 
 impl FromStr for Entry {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        const EMPTY_VEC: Vec<char> = Vec::new();
-        let mut patterns = [EMPTY_VEC; 10];
-        let mut codes = [EMPTY_VEC; 4];
+        let mut patterns = [Segments::default(); 10];
+        let mut codes = [Segments::default(); 4];
 
         let mut iter = s.split(' ');
         for pat in &mut patterns {
-            let mut info: Vec<char> = iter.next().unwrap().trim().chars().collect();
-            info.sort_unstable();
-            *pat = info;
+            *pat = iter.next().ok_or("Missing pattern")?.parse()?;
         }
         // skip the delimiter
         iter.next();
         for code in &mut codes {
-            let mut info: Vec<char> = iter.next().unwrap().trim().chars().collect();
-            info.sort_unstable();
-            *code = info;
+            *code = iter.next().ok_or("Missing code")?.parse()?;
         }
 
-        patterns.sort_by(|a, b| a.len().cmp(&b.len()));
-
         Ok(Entry { patterns, codes })
     }
 }
 
-impl std::fmt::Debug for Entry {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl fmt::Debug for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str("patterns: ")?;
         for pat in &self.patterns {
-            for c in pat {
-                f.write_char(*c)?;
-            }
-            f.write_str(", ")?;
+            write!(f, "{}, ", pat)?;
         }
         f.write_str("\ncodes: ")?;
-        for pat in &self.codes {
-            for c in pat {
-                f.write_char(*c)?;
-            }
-            f.write_str(", ")?;
+        for code in &self.codes {
+            write!(f, "{}, ", code)?;
         }
         Ok(())
     }
 }
 
 fn main() {
-    let mut entries: Vec<Entry> = input::read_lines("puzzles/day8.txt");
+    // every line is parsed on its own so a bad one only shows up as a fault
+    let lines: Vec<String> = input::read_lines("puzzles/day8.txt");
+    let entries: Vec<Result<Entry, &'static str>> = lines.iter().map(|l| l.parse()).collect();
     let solution = solve1(&entries);
     println!("solution 1: {}", solution);
-    let solution = solve2(&mut entries);
-    println!("solution 2: {}", solution);
+    let summary = solve2(&entries, &DigitTable::standard());
+    println!("solution 2: {}", summary.total);
+    if !summary.faults.is_empty() {
+        print!("{}", summary);
+    }
 }
 
-fn solve1(entries: &[Entry]) -> usize {
+fn solve1(entries: &[Result<Entry, &'static str>]) -> usize {
     let mut digits = 0;
-    for entry in entries {
+    for entry in entries.iter().flatten() {
         digits += entry
             .codes
             .iter()
@@ -73,108 +127,351 @@ fn solve1(entries: &[Entry]) -> usize {
     digits
 }
 
-fn solve2(entries: &mut [Entry]) -> usize {
-    let mut result = 0;
-    for entry in entries {
-        arrange_numbers(&mut entry.patterns);
-        result += calc_code(entry);
+// Decodes every display it can, faulty ones are collected with the reason
+// instead of stopping the whole file.
+fn solve2(entries: &[Result<Entry, &'static str>], table: &DigitTable) -> Summary {
+    let mut summary = Summary::default();
+    for (idx, entry) in entries.iter().enumerate() {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                summary.faults.push((idx, Fault::Parse(e)));
+                continue;
+            }
+        };
+        match decode(entry, table) {
+            Ok(value) => {
+                summary.decoded += 1;
+                summary.total += value;
+            }
+            Err(e) => summary.faults.push((idx, Fault::Wiring(e))),
+        }
+    }
+    summary
+}
+
+#[derive(Debug, PartialEq)]
+enum Fault {
+    Parse(&'static str),
+    Wiring(WiringError),
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fault::Parse(e) => write!(f, "can't be read: {}", e),
+            Fault::Wiring(e) => write!(f, "{}", e),
+        }
     }
-    result
 }
 
-fn calc_code(entry: &Entry) -> usize {
+#[derive(Debug, Default, PartialEq)]
+struct Summary {
+    decoded: usize,
+    total: usize,
+    // Index of each faulty entry and what's wrong with it
+    faults: Vec<(usize, Fault)>,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "decoded {} of {} displays",
+            self.decoded,
+            self.decoded + self.faults.len()
+        )?;
+        for (idx, fault) in &self.faults {
+            writeln!(f, "  entry {}: {}", idx + 1, fault)?;
+        }
+        Ok(())
+    }
+}
+
+fn decode(entry: &Entry, table: &DigitTable) -> Result<usize, WiringError> {
+    let wiring = solve_wiring(&entry.patterns, table).map_err(|e| match e {
+        WiringError::Inconsistent => diagnose(&entry.patterns, table, None).unwrap_or(e),
+        WiringError::DuplicatePattern { first, second, .. } => WiringError::DuplicatePattern {
+            first,
+            second,
+            missing: diagnose(&entry.patterns, table, Some(second)).and_then(|e| match e {
+                WiringError::MissingDigit { digit, .. } => Some(digit),
+                _ => None,
+            }),
+        },
+        e => e,
+    })?;
+    calc_code(entry, &Decoder::new(&wiring, table))
+}
+
+fn calc_code(entry: &Entry, decoder: &Decoder) -> Result<usize, WiringError> {
     let mut result = 0;
-    for code in &entry.codes {
+    for (idx, code) in entry.codes.iter().enumerate() {
         result *= 10;
-        let value = entry.patterns.iter().position(|pat| pat == code).unwrap();
+        let value = decoder.digit(*code).ok_or(WiringError::UnknownCode {
+            position: idx,
+            code: *code,
+        })?;
         result += value;
     }
-    result
-}
-
-// Assumes patterns and letters in them are sorted
-fn arrange_numbers(patterns: &mut [Vec<char>; 10]) {
-    // Put 7, 1, 4 and 8 in the correct place
-    patterns.swap(1, 7);
-    patterns.swap(0, 1);
-    patterns.swap(2, 4);
-    patterns.swap(9, 8);
-    // 9 is the only 6-length that contains all of 4
-    let nine = patterns
-        .iter()
-        .enumerate()
-        .filter(|(_, pat)| pat.len() == 6)
-        .find(|(_, pat)| contains_all(*pat, &patterns[4]))
-        .map(|(idx, _)| idx)
-        .unwrap();
-    patterns.swap(nine, 9);
-    // 6 is the only 6-length that doesn't contain 7
-    let six = patterns
-        .iter()
-        .enumerate()
-        .filter(|(_, pat)| pat.len() == 6)
-        .find(|(_, pat)| !contains_all(*pat, &patterns[7]))
-        .map(|(idx, _)| idx)
-        .unwrap();
-    patterns.swap(six, 6);
-    // 0 is the only 6-length left
-    let zero = patterns
-        .iter()
-        .enumerate()
-        .find(|(_, pat)| (*pat != &patterns[9] && *pat != &patterns[6]))
-        .map(|(idx, _)| idx)
-        .unwrap();
-    patterns.swap(zero, 0);
-    // Correct ones by now: 0, 1, 4, 6, 7, 8, 9
-    // only 2, 3 and 5 left
-    // 3 is the only 5-length that contains all of 7
-    let three = patterns
-        .iter()
-        .enumerate()
-        .filter(|(_, pat)| pat.len() == 5)
-        .find(|(_, pat)| contains_all(*pat, &patterns[7]))
-        .map(|(idx, _)| idx)
-        .unwrap();
-    patterns.swap(three, 3);
-    // 5 is contained within 6
-    let five = patterns
-        .iter()
-        .enumerate()
-        .filter(|(_, pat)| pat.len() == 5)
-        .find(|(_, pat)| contains_all(&patterns[6], *pat))
-        .map(|(idx, _)| idx)
-        .unwrap();
-    patterns.swap(five, 5);
-    // 2 is already ordered
-}
-
-// Assumes both slices are sorted
-fn contains_all<T: PartialEq>(superset: &[T], subset: &[T]) -> bool {
-    if subset.len() > superset.len() {
-        return false;
-    }
-
-    let mut superset_pos = 0;
-    for c in subset.iter() {
-        while match superset.get(superset_pos) {
-            Some(cs) if cs == c => false,
-            None => return false,
-            Some(_) => true,
-        } {
-            superset_pos += 1;
-        }
-    }
-    true
+    Ok(result)
+}
+
+// Looks for the one digit whose pattern is missing: taking that digit out of
+// the table and a bad pattern out of the patterns leaves a display with a
+// single wiring. Without a `suspect` every pattern is tried as the bad one.
+fn diagnose(
+    patterns: &[Segments],
+    table: &DigitTable,
+    suspect: Option<usize>,
+) -> Option<WiringError> {
+    let mut found = None;
+    for digit in 0..table.digits.len() {
+        let reduced = DigitTable {
+            digits: [&table.digits[..digit], &table.digits[digit + 1..]].concat(),
+        };
+        for pattern in suspect.map_or(0..patterns.len(), |p| p..p + 1) {
+            let rest = [&patterns[..pattern], &patterns[pattern + 1..]].concat();
+            if let Ok(wiring) = solve_wiring(&rest, &reduced) {
+                // different digits could be missing, so none can be blamed
+                if matches!(found, Some(WiringError::MissingDigit { digit: d, .. }) if d != digit) {
+                    return None;
+                }
+                found = Some(WiringError::MissingDigit {
+                    digit,
+                    pattern,
+                    lit: wiring.segments(patterns[pattern]),
+                });
+            }
+        }
+    }
+    found
+}
+
+const SEGMENTS: usize = 7;
+
+// The segments that light up for each digit, indexed by the digit's value.
+struct DigitTable {
+    digits: Vec<Segments>,
+}
+
+impl DigitTable {
+    pub fn new(digits: &[&str]) -> Self {
+        let digits = digits
+            .iter()
+            .map(|d| d.parse().expect("invalid digit segments"))
+            .collect();
+        DigitTable { digits }
+    }
+    pub fn standard() -> Self {
+        DigitTable::new(&[
+            "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+        ])
+    }
+    pub fn digit(&self, segments: Segments) -> Option<usize> {
+        self.digits.iter().position(|&d| d == segments)
+    }
+}
+
+// Wire i drives segment `self.0[i]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Wiring([usize; SEGMENTS]);
+
+impl Wiring {
+    // The segments lit by a set of wires.
+    pub fn segments(&self, wires: Segments) -> Segments {
+        wires.iter().fold(Segments::default(), |acc, w| {
+            acc | Segments::single(self.0[w])
+        })
+    }
+    // The wires that light a set of segments.
+    pub fn wires(&self, segments: Segments) -> Segments {
+        (0..SEGMENTS)
+            .filter(|&w| segments.contains(self.0[w]))
+            .fold(Segments::default(), |acc, w| acc | Segments::single(w))
+    }
+}
+
+// The digit shown by every combination of wires under one wiring, so
+// decoding a code is a single lookup.
+struct Decoder([Option<u8>; 1 << SEGMENTS]);
+
+impl Decoder {
+    pub fn new(wiring: &Wiring, table: &DigitTable) -> Self {
+        let mut lookup = [None; 1 << SEGMENTS];
+        for (value, &digit) in table.digits.iter().enumerate() {
+            lookup[wiring.wires(digit).0 as usize] = Some(value as u8);
+        }
+        Decoder(lookup)
+    }
+    pub fn digit(&self, wires: Segments) -> Option<usize> {
+        self.0[wires.0 as usize].map(|d| d as usize)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WiringError {
+    // No wiring maps the patterns onto the digit table
+    Inconsistent,
+    // More than one wiring does
+    Ambiguous,
+    // Two patterns light the same wires, so some digit has no pattern
+    DuplicatePattern {
+        first: usize,
+        second: usize,
+        missing: Option<usize>,
+    },
+    // Every digit but this one has a pattern; the pattern at `pattern`
+    // lights `lit` instead
+    MissingDigit {
+        digit: usize,
+        pattern: usize,
+        lit: Segments,
+    },
+    // The code at this position doesn't show any digit
+    UnknownCode {
+        position: usize,
+        code: Segments,
+    },
+}
+
+impl fmt::Display for WiringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WiringError::Inconsistent => write!(f, "no wiring matches the patterns"),
+            WiringError::Ambiguous => write!(f, "more than one wiring matches the patterns"),
+            WiringError::DuplicatePattern {
+                first,
+                second,
+                missing,
+            } => {
+                write!(f, "patterns {} and {} are the same", first + 1, second + 1)?;
+                match missing {
+                    Some(digit) => write!(f, ", so there's no pattern for {}", digit),
+                    None => Ok(()),
+                }
+            }
+            WiringError::MissingDigit {
+                digit,
+                pattern,
+                lit,
+            } => write!(
+                f,
+                "there's no pattern for {}, pattern {} lights segments {} which aren't a digit",
+                digit,
+                pattern + 1,
+                lit
+            ),
+            WiringError::UnknownCode { position, code } => {
+                write!(f, "code {} ({}) matches no pattern", position + 1, code)
+            }
+        }
+    }
+}
+
+// For each possible set size, how many of `sets` containing `idx` have it.
+fn signature(sets: &[Segments], idx: usize) -> [u8; SEGMENTS + 1] {
+    let mut lens = [0; SEGMENTS + 1];
+    for set in sets.iter().filter(|s| s.contains(idx)) {
+        lens[set.len()] += 1;
+    }
+    lens
+}
+
+// Finds the one wire -> segment permutation that turns every pattern into a
+// distinct digit of the table. Each wire can only go to a segment that's
+// used by digits of the same sizes as the patterns the wire is in; the rest
+// is a backtracking search that drops any partial wiring which can't grow
+// into a digit.
+fn solve_wiring(patterns: &[Segments], table: &DigitTable) -> Result<Wiring, WiringError> {
+    for (second, p) in patterns.iter().enumerate() {
+        if let Some(first) = patterns[..second].iter().position(|q| q == p) {
+            return Err(WiringError::DuplicatePattern {
+                first,
+                second,
+                missing: None,
+            });
+        }
+    }
+    if patterns.len() != table.digits.len() {
+        return Err(WiringError::Inconsistent);
+    }
+
+    let mut candidates = [Segments::default(); SEGMENTS];
+    for (wire, candidate) in candidates.iter_mut().enumerate() {
+        let wire_sig = signature(patterns, wire);
+        for seg in 0..SEGMENTS {
+            if signature(&table.digits, seg) == wire_sig {
+                *candidate = *candidate | Segments::single(seg);
+            }
+        }
+    }
+
+    let mut first = None;
+    let mut assigned = [0; SEGMENTS];
+    match search(0, &mut assigned, &candidates, patterns, table, &mut first) {
+        0 => Err(WiringError::Inconsistent),
+        1 => Ok(first.unwrap()),
+        _ => Err(WiringError::Ambiguous),
+    }
+}
+
+// Returns how many complete wirings were found, stopping at two since that's
+// enough to know it's ambiguous.
+fn search(
+    wire: usize,
+    assigned: &mut [usize; SEGMENTS],
+    candidates: &[Segments; SEGMENTS],
+    patterns: &[Segments],
+    table: &DigitTable,
+    first: &mut Option<Wiring>,
+) -> usize {
+    if wire == SEGMENTS {
+        // the patterns are distinct and the wiring is a permutation, so
+        // every pattern landing on a digit means they all land on different ones
+        let wiring = Wiring(*assigned);
+        if patterns
+            .iter()
+            .all(|&p| table.digit(wiring.segments(p)).is_some())
+        {
+            first.get_or_insert(wiring);
+            return 1;
+        }
+        return 0;
+    }
+
+    let mut found = 0;
+    let known = Segments(((1 << (wire + 1)) - 1) as u8);
+    for seg in candidates[wire].iter() {
+        if assigned[..wire].contains(&seg) {
+            continue;
+        }
+        assigned[wire] = seg;
+        let wiring = Wiring(*assigned);
+        let fits = patterns.iter().all(|&p| {
+            let partial = wiring.segments(p & known);
+            table
+                .digits
+                .iter()
+                .any(|d| d.len() == p.len() && partial.is_subset(*d))
+        });
+        if fits {
+            found += search(wire + 1, assigned, candidates, patterns, table, first);
+            if found > 1 {
+                break;
+            }
+        }
+    }
+    found
 }
 
 #[test]
 fn test_short() {
-    let mut entries = vec![Entry::from_str(
+    let entries = vec![Entry::from_str(
         "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe",
-    )
-    .unwrap()];
-    let result = solve2(&mut entries);
-    assert_eq!(result, 8394);
+    )];
+    let result = solve2(&entries, &DigitTable::standard());
+    assert_eq!(result.total, 8394);
 }
 
 #[test]
@@ -190,21 +487,137 @@ dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbc
 bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef
 egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
 gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce";
-    let mut entries: Vec<Entry> = input.lines().map(|l| Entry::from_str(l).unwrap()).collect();
-    let result = solve2(&mut entries);
-    assert_eq!(result, 61229);
+    let entries: Vec<Result<Entry, _>> = input.lines().map(Entry::from_str).collect();
+    let result = solve2(&entries, &DigitTable::standard());
+    assert_eq!(result.total, 61229);
+    assert_eq!(result.decoded, 10);
+}
+
+#[test]
+fn test_segments() {
+    let superset = Segments::from_str("abcd").unwrap();
+    let subset = Segments::from_str("cab").unwrap();
+    assert!(subset.is_subset(superset));
+    let subset = Segments::from_str("bc").unwrap();
+    assert!(subset.is_subset(superset));
+    let subset = Segments::from_str("be").unwrap();
+    assert!(!subset.is_subset(superset));
+    let superset = Segments::from_str("bcde").unwrap();
+    let subset = Segments::from_str("ace").unwrap();
+    assert!(!subset.is_subset(superset));
+
+    assert_eq!(superset.len(), 4);
+    assert_eq!((superset | subset).to_string(), "abcde");
+    assert_eq!((superset & subset).to_string(), "ce");
+    assert_eq!(Segments::from_str("gfedcba").unwrap(), Segments(0b111_1111));
+    assert!(Segments::from_str("abh").is_err());
 }
 
 #[test]
-fn test_contains_all() {
-    let superset = [1, 2, 3, 4];
-    let subset = [1, 2, 3];
-    assert!(contains_all(&superset, &subset));
-    let subset = [2, 3];
-    assert!(contains_all(&superset, &subset));
-    let subset = [2, 5];
-    assert!(!contains_all(&superset, &subset));
-    let superset = [2, 3, 4, 5];
-    let subset = [1, 3, 5];
-    assert!(!contains_all(&superset, &subset));
+fn test_wiring() {
+    let table = DigitTable::standard();
+    let entry = Entry::from_str(
+        "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+    )
+    .unwrap();
+    let wiring = solve_wiring(&entry.patterns, &table).unwrap();
+    assert_eq!(wiring, Wiring([2, 5, 6, 0, 1, 3, 4]));
+    assert_eq!(calc_code(&entry, &Decoder::new(&wiring, &table)), Ok(5353));
+
+    // the same display with its segments labelled the other way round
+    let flip = |seg: usize| SEGMENTS - 1 - seg;
+    let flipped = DigitTable {
+        digits: table
+            .digits
+            .iter()
+            .map(|d| Segments(d.0.reverse_bits() >> 1))
+            .collect(),
+    };
+    let flipped_wiring = solve_wiring(&entry.patterns, &flipped).unwrap();
+    assert_eq!(flipped_wiring.0, wiring.0.map(flip));
+    assert_eq!(
+        calc_code(&entry, &Decoder::new(&flipped_wiring, &flipped)),
+        Ok(5353)
+    );
+
+    // a 3 that lights the segments of a 2 can't match any wiring
+    let mut broken = table.digits.clone();
+    broken[3] = broken[2];
+    let broken = DigitTable { digits: broken };
+    assert_eq!(
+        solve_wiring(&entry.patterns, &broken),
+        Err(WiringError::Inconsistent)
+    );
+
+    // with only 1 and 8 there's no telling which wire is which
+    let patterns = [Segments::from_str("ab").unwrap(), Segments(0b111_1111)];
+    assert_eq!(
+        solve_wiring(&patterns, &DigitTable::new(&["cf", "abcdefg"])),
+        Err(WiringError::Ambiguous)
+    );
+
+    // a code that isn't one of the patterns
+    let mut entry = entry;
+    entry.codes[2] = Segments::from_str("abc").unwrap();
+    assert_eq!(
+        calc_code(&entry, &Decoder::new(&wiring, &table)),
+        Err(WiringError::UnknownCode {
+            position: 2,
+            code: Segments::from_str("abc").unwrap()
+        })
+    );
+}
+
+#[test]
+fn test_faulty() {
+    let table = DigitTable::standard();
+    let input =
+        "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf
+acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb dab | cdfeb fcadb cdfeb cdbaf
+acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ag | cdfeb fcadb cdfeb cdbaf
+acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfe cdbaf
+acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb
+be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe";
+    let entries: Vec<Result<Entry, _>> = input.lines().map(Entry::from_str).collect();
+    let summary = solve2(&entries, &table);
+    assert_eq!(summary.decoded, 2);
+    assert_eq!(summary.total, 5353 + 8394);
+    assert_eq!(
+        summary.faults,
+        vec![
+            (
+                1,
+                Fault::Wiring(WiringError::DuplicatePattern {
+                    first: 4,
+                    second: 9,
+                    missing: Some(1)
+                })
+            ),
+            (
+                2,
+                Fault::Wiring(WiringError::MissingDigit {
+                    digit: 1,
+                    pattern: 9,
+                    lit: Segments::from_str("ce").unwrap()
+                })
+            ),
+            (
+                3,
+                Fault::Wiring(WiringError::UnknownCode {
+                    position: 2,
+                    code: Segments::from_str("cdef").unwrap()
+                })
+            ),
+            (4, Fault::Parse("Missing code")),
+        ]
+    );
+    assert_eq!(
+        summary.to_string(),
+        "decoded 2 of 6 displays
+  entry 2: patterns 5 and 10 are the same, so there's no pattern for 1
+  entry 3: there's no pattern for 1, pattern 10 lights segments ce which aren't a digit
+  entry 4: code 3 (cdef) matches no pattern
+  entry 5: can't be read: Missing code
+"
+    );
 }
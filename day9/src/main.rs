@@ -1,127 +1,435 @@
-use std::io::BufRead;
+use std::{collections::VecDeque, fmt, io::BufRead};
 
-struct Map<const RS: usize> {
-    pub points: Vec<[u8; RS]>,
+struct Map {
+    width: usize,
+    height: usize,
+    // row by row
+    points: Vec<u8>,
 }
 
-impl<const RS: usize> Map<RS> {
-    // [left, right, up, down]
-    pub fn adjacent(&self, coords: (usize, usize)) -> [Option<u8>; 4] {
-        let mut neighbors = [None; 4];
-        if coords.0 != 0 {
-            if let Some(n) = self.points[coords.1].get(coords.0 - 1) {
-                neighbors[0] = Some(*n);
+#[derive(Debug, PartialEq)]
+enum MapError {
+    Empty,
+    // The row doesn't have as many columns as the first one
+    RaggedRow {
+        row: usize,
+        len: usize,
+        width: usize,
+    },
+    InvalidHeight {
+        row: usize,
+        col: usize,
+        c: char,
+    },
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Empty => write!(f, "the map has no points"),
+            MapError::RaggedRow { row, len, width } => write!(
+                f,
+                "row {} has {} columns but the map is {} wide",
+                row + 1,
+                len,
+                width
+            ),
+            MapError::InvalidHeight { row, col, c } => {
+                write!(
+                    f,
+                    "'{}' at row {}, column {} is not a height",
+                    c,
+                    row + 1,
+                    col + 1
+                )
             }
         }
-        if let Some(n) = self.points[coords.1].get(coords.0 + 1) {
-            neighbors[1] = Some(*n);
-        }
-        if coords.1 != 0 {
-            if let Some(row) = self.points.get(coords.1 - 1) {
-                neighbors[2] = Some(row[coords.0]);
+    }
+}
+
+impl Map {
+    // The width is taken from the first row, every other row has to match it.
+    pub fn new<I: AsRef<str>>(lines: impl Iterator<Item = I>) -> Result<Self, MapError> {
+        let mut width = 0;
+        let mut height = 0;
+        let mut points = Vec::new();
+        for (row, line) in lines.enumerate() {
+            let line = line.as_ref().trim_end();
+            if row == 0 {
+                width = line.chars().count();
+            }
+            if line.chars().count() != width {
+                return Err(MapError::RaggedRow {
+                    row,
+                    len: line.chars().count(),
+                    width,
+                });
             }
+            for (col, c) in line.chars().enumerate() {
+                match c.to_digit(10) {
+                    Some(h) => points.push(h as u8),
+                    None => return Err(MapError::InvalidHeight { row, col, c }),
+                }
+            }
+            height += 1;
         }
-        if let Some(row) = self.points.get(coords.1 + 1) {
-            neighbors[3] = Some(row[coords.0]);
+        if width == 0 {
+            return Err(MapError::Empty);
         }
-        neighbors
+        Ok(Map {
+            width,
+            height,
+            points,
+        })
     }
-    pub fn get_mut(&mut self, coords: (usize, usize)) -> Option<&mut u8> {
-        if let Some(row) = self.points.get_mut(coords.1) {
-            row.get_mut(coords.0)
+    pub fn get(&self, coords: (usize, usize)) -> Option<u8> {
+        if coords.0 < self.width && coords.1 < self.height {
+            Some(self.points[coords.1 * self.width + coords.0])
         } else {
             None
         }
     }
+    // The coordinates of the neighbouring points that are on the map: left,
+    // right, above and below, then the diagonals with `Connectivity::Eight`.
+    pub fn neighbours(
+        &self,
+        coords: (usize, usize),
+        connectivity: Connectivity,
+    ) -> impl Iterator<Item = (usize, usize)> {
+        let (x, y) = (coords.0 as isize, coords.1 as isize);
+        let (width, height) = (self.width as isize, self.height as isize);
+        let offsets: &[(isize, isize)] = match connectivity {
+            Connectivity::Four => &[(-1, 0), (1, 0), (0, -1), (0, 1)],
+            Connectivity::Eight => &[
+                (-1, 0),
+                (1, 0),
+                (0, -1),
+                (0, 1),
+                (-1, -1),
+                (1, -1),
+                (-1, 1),
+                (1, 1),
+            ],
+        };
+        offsets
+            .iter()
+            .map(move |&(dx, dy)| (x + dx, y + dy))
+            .filter(move |&(x, y)| 0 <= x && x < width && 0 <= y && y < height)
+            .map(|(x, y)| (x as usize, y as usize))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Connectivity {
+    Four,
+    Eight,
+}
+
+// Basins are walled off by every point of `height` or higher.
+fn wall_at(height: u8) -> impl Fn(u8) -> bool {
+    move |h| h >= height
 }
 
 fn main() {
-    let input: Vec<[u8; 100]> = input::read_file("puzzles/day9.txt")
-        .lines()
-        .map(|l| {
-            let mut row = [0; 100];
-            l.unwrap()
-                .chars()
-                .map(|c| c as u8 - b'0')
-                .enumerate()
-                .for_each(|(idx, c)| row[idx] = c);
-            row
-        })
-        .collect();
-    let mut map = Map { points: input };
+    let map = Map::new(
+        input::read_file("puzzles/day9.txt")
+            .lines()
+            .map(|l| l.unwrap()),
+    );
+    let map = match map {
+        Ok(map) => map,
+        Err(e) => {
+            println!("invalid heightmap: {}", e);
+            return;
+        }
+    };
     println!("solution 1: {}", solve1(&map));
-    println!("solution 2: {}", solve2(&mut map));
+    println!("solution 2: {}", solve2(&map));
+
+    // `day9 basins [4|8] [wall height]` also draws the basins
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(|s| s.as_str()) == Some("basins") {
+        let connectivity = match args.get(1).map(|s| s.as_str()) {
+            Some("8") => Connectivity::Eight,
+            _ => Connectivity::Four,
+        };
+        let wall = args
+            .get(2)
+            .map(|h| h.parse().expect("failed to parse wall height"))
+            .unwrap_or(9);
+        print!("{}", label_basins(&map, connectivity, wall_at(wall)));
+    }
 }
 
-fn solve1<const RS: usize>(map: &Map<RS>) -> usize {
-    let mut levels = 0;
-    for y in 0..map.points.len() {
-        for x in 0..RS {
-            let num = map.points[y][x];
-            if map.adjacent((x, y)).into_iter().flatten().all(|c| c > num) {
-                levels += num as usize + 1;
+fn solve1(map: &Map) -> usize {
+    low_points(map, Connectivity::Four)
+        .iter()
+        .map(|&coords| map.get(coords).unwrap() as usize + 1)
+        .sum()
+}
+fn solve2(map: &Map) -> usize {
+    let mut sizes: Vec<usize> = label_basins(map, Connectivity::Four, wall_at(9))
+        .basins
+        .iter()
+        .map(|b| b.size)
+        .collect();
+    sizes.sort_unstable();
+    sizes.iter().rev().take(3).product()
+}
+
+// Points lower than all of their neighbours.
+fn low_points(map: &Map, connectivity: Connectivity) -> Vec<(usize, usize)> {
+    let mut points = Vec::new();
+    for y in 0..map.height {
+        for x in 0..map.width {
+            let num = map.get((x, y)).unwrap();
+            if map
+                .neighbours((x, y), connectivity)
+                .all(|n| map.get(n).unwrap() > num)
+            {
+                points.push((x, y));
             }
         }
     }
-    levels
-}
-fn solve2<const RS: usize>(map: &mut Map<RS>) -> usize {
-    let mut fill = 10;
-    for y in 0..map.points.len() {
-        for x in 0..RS {
-            let val = map.points[y][x];
-            if val < 9 {
-                fill += 1;
-                flood_fill((x, y), map, fill);
+    points
+}
+
+#[derive(Debug, PartialEq)]
+struct Basin {
+    size: usize,
+    // The lowest point of the basin, the first one in reading order if
+    // there's a tie
+    low_point: (usize, usize),
+    // Top left and bottom right corners of the bounding box
+    min: (usize, usize),
+    max: (usize, usize),
+}
+
+struct Basins {
+    width: usize,
+    // The basin every point belongs to, `None` for walls
+    labels: Vec<Option<usize>>,
+    basins: Vec<Basin>,
+}
+
+impl Basins {
+    pub fn label(&self, coords: (usize, usize)) -> Option<usize> {
+        self.labels[coords.1 * self.width + coords.0]
+    }
+}
+
+// The label map, with basins lettered a to z (repeating) and walls as `#`.
+impl fmt::Display for Basins {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.labels.len() / self.width {
+            for x in 0..self.width {
+                match self.label((x, y)) {
+                    Some(label) => write!(f, "{}", (b'a' + (label % 26) as u8) as char)?,
+                    None => write!(f, "#")?,
+                }
             }
+            writeln!(f)?;
         }
+        Ok(())
     }
-    let mut basins: Vec<usize> = vec![0; fill as usize - 9];
-    map.points.iter().flat_map(|a| a.iter()).for_each(|c| {
-        if *c >= 10 {
-            basins[*c as usize - 10] += 1
-        }
-    });
-    basins.sort_unstable();
-    basins.iter().rev().take(3).product()
 }
 
-fn flood_fill<const RS: usize>(coords: (usize, usize), map: &mut Map<RS>, num: u8) {
-    std::io::Write::flush(&mut std::io::stdout().lock()).unwrap();
-    if let Some(node) = map.get_mut(coords) {
-        if *node == 9 || *node >= num {
-            return;
-        }
-        *node = num;
-        flood_fill((coords.0, coords.1 + 1), map, num);
-        flood_fill((coords.0 + 1, coords.1), map, num);
-        if coords.1 != 0 {
-            flood_fill((coords.0, coords.1 - 1), map, num);
-        }
-        if coords.0 != 0 {
-            flood_fill((coords.0 - 1, coords.1), map, num);
+// Splits the map into basins bounded by the points `is_wall` picks, with a
+// breadth-first search from every point that isn't labelled yet.
+fn label_basins(map: &Map, connectivity: Connectivity, is_wall: impl Fn(u8) -> bool) -> Basins {
+    let mut labels = vec![None; map.points.len()];
+    let mut basins = Vec::new();
+    let mut queue = VecDeque::new();
+
+    for y in 0..map.height {
+        for x in 0..map.width {
+            if is_wall(map.get((x, y)).unwrap()) || labels[y * map.width + x].is_some() {
+                continue;
+            }
+            let label = basins.len();
+            let mut basin = Basin {
+                size: 0,
+                low_point: (x, y),
+                min: (x, y),
+                max: (x, y),
+            };
+            labels[y * map.width + x] = Some(label);
+            queue.push_back((x, y));
+
+            while let Some(coords) = queue.pop_front() {
+                basin.size += 1;
+                if map.get(coords) < map.get(basin.low_point)
+                    || map.get(coords) == map.get(basin.low_point)
+                        && (coords.1, coords.0) < (basin.low_point.1, basin.low_point.0)
+                {
+                    basin.low_point = coords;
+                }
+                basin.min = (basin.min.0.min(coords.0), basin.min.1.min(coords.1));
+                basin.max = (basin.max.0.max(coords.0), basin.max.1.max(coords.1));
+
+                for next in map.neighbours(coords, connectivity) {
+                    let idx = next.1 * map.width + next.0;
+                    if !is_wall(map.get(next).unwrap()) && labels[idx].is_none() {
+                        labels[idx] = Some(label);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            basins.push(basin);
         }
     }
+
+    Basins {
+        width: map.width,
+        labels,
+        basins,
+    }
 }
 
 #[test]
 fn test1() {
-    let input: Vec<[u8; 10]> = "2199943210
+    let input = "2199943210
 3987894921
 9856789892
 8767896789
-9899965678"
-        .lines()
-        .map(|l| {
-            let mut row = [0; 10];
-            l.chars()
-                .map(|c| c as u8 - b'0')
-                .enumerate()
-                .for_each(|(idx, c)| row[idx] = c);
-            row
+9899965678";
+    let map = Map::new(input.lines()).unwrap();
+    assert_eq!(solve1(&map), 15);
+    assert_eq!(solve2(&map), 1134);
+}
+
+#[test]
+fn test_dimensions() {
+    let map = Map::new("2193\n3989\n9859\n8769\n".lines()).unwrap();
+    assert_eq!((map.width, map.height), (4, 4));
+    assert_eq!(
+        map.neighbours((2, 3), Connectivity::Four)
+            .collect::<Vec<_>>(),
+        vec![(1, 3), (3, 3), (2, 2)]
+    );
+    assert_eq!(solve1(&map), 2 + 4 + 6);
+    assert_eq!(solve2(&map), 18);
+
+    assert_eq!(
+        Map::new("2199\n398\n".lines()).err(),
+        Some(MapError::RaggedRow {
+            row: 1,
+            len: 3,
+            width: 4
         })
+    );
+    assert_eq!(
+        Map::new("21\n3x\n".lines()).err(),
+        Some(MapError::InvalidHeight {
+            row: 1,
+            col: 1,
+            c: 'x'
+        })
+    );
+    assert_eq!(
+        Map::new("2é\n31\n".lines()).err(),
+        Some(MapError::InvalidHeight {
+            row: 0,
+            col: 1,
+            c: 'é'
+        })
+    );
+    assert_eq!(
+        Map::new("2199\n398\n".lines()).err().unwrap().to_string(),
+        "row 2 has 3 columns but the map is 4 wide"
+    );
+    assert_eq!(
+        Map::new("2é\n31\n".lines()).err().unwrap().to_string(),
+        "'é' at row 1, column 2 is not a height"
+    );
+    assert_eq!(Map::new("".lines()).err(), Some(MapError::Empty));
+}
+
+#[test]
+fn test_basins() {
+    let input = "2199943210
+3987894921
+9856789892
+8767896789
+9899965678";
+    let map = Map::new(input.lines()).unwrap();
+    let basins = label_basins(&map, Connectivity::Four, wall_at(9));
+    assert_eq!(basins.basins.len(), 4);
+    assert_eq!(
+        basins.basins[0],
+        Basin {
+            size: 3,
+            low_point: (1, 0),
+            min: (0, 0),
+            max: (1, 1)
+        }
+    );
+    assert_eq!(basins.basins[1].low_point, (9, 0));
+    assert_eq!(basins.basins[1].size, 9);
+    assert_eq!(basins.label((2, 2)), Some(2));
+    assert_eq!(basins.label((2, 0)), None);
+    // the heights are left alone
+    assert_eq!(map.get((2, 2)), Some(5));
+    assert_eq!(
+        basins.to_string(),
+        "aa###bbbbb\n\
+         a#ccc#b#bb\n\
+         #ccccc#d#b\n\
+         ccccc#ddd#\n\
+         #c###ddddd\n"
+    );
+
+    // a checkerboard of walls has a basin on every other point
+    let row: String = (0..300)
+        .map(|x| if x % 2 == 0 { '1' } else { '9' })
         .collect();
-    let mut map = Map { points: input };
-    assert_eq!(solve1(&map), 15);
-    assert_eq!(solve2(&mut map), 1134);
+    let map = Map::new([row.as_str(), &"9".repeat(300)].iter().cycle().take(300)).unwrap();
+    let basins = label_basins(&map, Connectivity::Four, wall_at(9));
+    assert_eq!(basins.basins.len(), 150 * 150);
+    assert!(basins.basins.iter().all(|b| b.size == 1));
+
+    // one basin covering a big map doesn't overflow the stack
+    let row = "0".repeat(1000);
+    let map = Map::new(std::iter::repeat_n(row.as_str(), 1000)).unwrap();
+    let basins = label_basins(&map, Connectivity::Four, wall_at(9));
+    assert_eq!(basins.basins.len(), 1);
+    assert_eq!(basins.basins[0].size, 1_000_000);
+    assert_eq!(basins.basins[0].max, (999, 999));
+}
+
+#[test]
+fn test_rules() {
+    let input = "2199943210
+3987894921
+9856789892
+8767896789
+9899965678";
+    let map = Map::new(input.lines()).unwrap();
+    assert_eq!(
+        map.neighbours((0, 0), Connectivity::Eight)
+            .collect::<Vec<_>>(),
+        vec![(1, 0), (0, 1), (1, 1)]
+    );
+    assert_eq!(map.neighbours((5, 2), Connectivity::Eight).count(), 8);
+
+    // the diagonals join all four basins
+    let basins = label_basins(&map, Connectivity::Eight, wall_at(9));
+    assert_eq!(basins.basins.len(), 1);
+    assert_eq!(basins.basins[0].size, 3 + 9 + 14 + 9);
+    assert_eq!(basins.basins[0].low_point, (9, 0));
+    assert_eq!(basins.basins[0].max, (9, 4));
+    assert_eq!(low_points(&map, Connectivity::Four).len(), 4);
+    assert_eq!(low_points(&map, Connectivity::Eight).len(), 4);
+
+    // lower walls split the map into smaller basins
+    let basins = label_basins(&map, Connectivity::Four, wall_at(7));
+    let mut sizes: Vec<usize> = basins.basins.iter().map(|b| b.size).collect();
+    sizes.sort_unstable();
+    assert_eq!(sizes, vec![3, 3, 4, 9]);
+
+    // any predicate works, here only even heights are open
+    let basins = label_basins(&map, Connectivity::Eight, |h| h % 2 == 1);
+    let sizes: Vec<usize> = basins.basins.iter().map(|b| b.size).collect();
+    assert_eq!(sizes, vec![1, 21]);
+    assert_eq!(basins.label((3, 3)), None);
 }